@@ -0,0 +1,411 @@
+//! Detection whether the user is at the desk, to pause the timer while they are away.
+//!
+//! The user counts as away while the session is locked or no input happened for the idle
+//! threshold. Backends:
+//!
+//! - `Logind`: the `LockedHint` of the current logind session, read from the system bus
+//! - `X11`: time since the last input from the X11 screen saver extension
+//! - `Mutter`: time since the last input from GNOME's `org.gnome.Mutter.IdleMonitor` on the
+//!   session bus, which also works on Wayland
+//! - `Auto`: logind together with Mutter on Wayland or X11 otherwise, whichever are available
+//!
+//! Other Wayland compositors than Mutter provide no idle time, there only the lock state counts.
+
+use crate::settings::ActivityBackend;
+
+use std::time::Duration;
+
+use iced::futures::{SinkExt, Stream};
+use thiserror::Error;
+use x11rb::connection::Connection as _;
+use x11rb::protocol::screensaver::ConnectionExt as _;
+use x11rb::rust_connection::RustConnection;
+use zbus::blocking::{Connection, Proxy, proxy};
+
+pub const ACTIVITY_CHECK_INTERVAL_AS_SEC: u64 = 5;
+
+pub const LOGIND_DESTINATION: &str = "org.freedesktop.login1";
+pub const LOGIND_SESSION_PATH: &str = "/org/freedesktop/login1/session/auto";
+pub const LOGIND_SESSION_INTERFACE: &str = "org.freedesktop.login1.Session";
+
+pub const MUTTER_DESTINATION: &str = "org.gnome.Mutter.IdleMonitor";
+pub const MUTTER_PATH: &str = "/org/gnome/Mutter/IdleMonitor/Core";
+pub const MUTTER_INTERFACE: &str = "org.gnome.Mutter.IdleMonitor";
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ActivityError {
+    #[error("D-Bus error: {0}")]
+    DBus(String),
+    #[error("X11 error: {0}")]
+    X11(String),
+    #[error("No activity source is available.")]
+    NoSource,
+}
+
+impl From<zbus::Error> for ActivityError {
+    fn from(err: zbus::Error) -> Self {
+        ActivityError::DBus(err.to_string())
+    }
+}
+
+/// Connect to the source(s) of the backend.
+pub fn connect(backend: ActivityBackend) -> Result<Box<dyn ActivitySource>, ActivityError> {
+    match backend {
+        ActivityBackend::Logind => Ok(Box::new(LogindSource::connect()?)),
+        ActivityBackend::X11 => Ok(Box::new(X11Source::connect()?)),
+        ActivityBackend::Mutter => Ok(Box::new(MutterSource::connect()?)),
+        ActivityBackend::Auto => {
+            let mut sources: Vec<Box<dyn ActivitySource>> = Vec::new();
+            if let Ok(source) = LogindSource::connect() {
+                sources.push(Box::new(source));
+            }
+            if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+                if let Ok(source) = MutterSource::connect() {
+                    sources.push(Box::new(source));
+                }
+            } else if let Ok(source) = X11Source::connect() {
+                sources.push(Box::new(source));
+            }
+
+            if sources.is_empty() {
+                Err(ActivityError::NoSource)
+            } else {
+                Ok(Box::new(CombinedSource(sources)))
+            }
+        }
+    }
+}
+
+/// What a source knows about the user at one point in time.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ActivitySample {
+    pub locked: bool,
+    /// Time since the last input, if the source knows it.
+    pub idle_for: Option<Duration>,
+}
+
+impl ActivitySample {
+    /// Combine the samples of two sources of the same session.
+    pub fn merge(self, other: ActivitySample) -> ActivitySample {
+        ActivitySample {
+            locked: self.locked || other.locked,
+            idle_for: match (self.idle_for, other.idle_for) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            },
+        }
+    }
+}
+
+pub trait ActivitySource: Send {
+    /// Query the current state. May block for a short time.
+    fn sample(&mut self) -> Result<ActivitySample, ActivityError>;
+}
+
+/// Whether the session is locked, from the `LockedHint` of a logind session.
+pub struct LogindSource {
+    proxy: Proxy<'static>,
+}
+
+impl LogindSource {
+    pub fn connect() -> Result<Self, ActivityError> {
+        Self::with_connection(&Connection::system()?, LOGIND_DESTINATION)
+    }
+
+    /// Read the session of the logind service `destination` on `connection`.
+    pub fn with_connection(
+        connection: &Connection,
+        destination: &'static str,
+    ) -> Result<Self, ActivityError> {
+        let proxy = proxy::Builder::new(connection)
+            .destination(destination)?
+            .path(LOGIND_SESSION_PATH)?
+            .interface(LOGIND_SESSION_INTERFACE)?
+            .cache_properties(zbus::proxy::CacheProperties::No)
+            .build()?;
+        Ok(Self { proxy })
+    }
+}
+
+impl ActivitySource for LogindSource {
+    fn sample(&mut self) -> Result<ActivitySample, ActivityError> {
+        Ok(ActivitySample {
+            locked: self.proxy.get_property("LockedHint")?,
+            idle_for: None,
+        })
+    }
+}
+
+/// Time since the last input, from the X11 screen saver extension.
+pub struct X11Source {
+    connection: RustConnection,
+    root: u32,
+}
+
+impl X11Source {
+    pub fn connect() -> Result<Self, ActivityError> {
+        let (connection, screen_num) = match x11rb::connect(None) {
+            Err(err) => return Err(ActivityError::X11(err.to_string())),
+            Ok(c) => c,
+        };
+        let root = connection.setup().roots[screen_num].root;
+        Ok(Self { connection, root })
+    }
+}
+
+impl ActivitySource for X11Source {
+    fn sample(&mut self) -> Result<ActivitySample, ActivityError> {
+        let info = self
+            .connection
+            .screensaver_query_info(self.root)
+            .map_err(|err| ActivityError::X11(err.to_string()))?
+            .reply()
+            .map_err(|err| ActivityError::X11(err.to_string()))?;
+        Ok(ActivitySample {
+            locked: false,
+            idle_for: Some(Duration::from_millis(info.ms_since_user_input.into())),
+        })
+    }
+}
+
+/// Time since the last input, from GNOME's idle monitor.
+pub struct MutterSource {
+    connection: Connection,
+    destination: &'static str,
+}
+
+impl MutterSource {
+    pub fn connect() -> Result<Self, ActivityError> {
+        let mut source = Self::with_connection(Connection::session()?, MUTTER_DESTINATION);
+        // Fail early if there is no idle monitor on the bus.
+        source.sample()?;
+        Ok(source)
+    }
+
+    pub fn with_connection(connection: Connection, destination: &'static str) -> Self {
+        Self {
+            connection,
+            destination,
+        }
+    }
+}
+
+impl ActivitySource for MutterSource {
+    fn sample(&mut self) -> Result<ActivitySample, ActivityError> {
+        let reply = self.connection.call_method(
+            Some(self.destination),
+            MUTTER_PATH,
+            Some(MUTTER_INTERFACE),
+            "GetIdletime",
+            &(),
+        )?;
+        let idle_as_ms: u64 = reply.body().deserialize()?;
+        Ok(ActivitySample {
+            locked: false,
+            idle_for: Some(Duration::from_millis(idle_as_ms)),
+        })
+    }
+}
+
+/// Several sources of the same session, merged into one sample.
+pub struct CombinedSource(pub Vec<Box<dyn ActivitySource>>);
+
+impl ActivitySource for CombinedSource {
+    fn sample(&mut self) -> Result<ActivitySample, ActivityError> {
+        let mut merged = ActivitySample::default();
+        for source in &mut self.0 {
+            merged = merged.merge(source.sample()?);
+        }
+        Ok(merged)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Presence {
+    Away,
+    Back,
+}
+
+/// Turns samples into changes of the user's presence.
+#[derive(Debug, Default)]
+pub struct AwayDetector {
+    away: bool,
+}
+
+impl AwayDetector {
+    pub fn is_away(&self) -> bool {
+        self.away
+    }
+
+    /// The change of presence shown by `sample`, if any.
+    pub fn update(
+        &mut self,
+        sample: &ActivitySample,
+        idle_threshold: Duration,
+    ) -> Option<Presence> {
+        let away = sample.locked || sample.idle_for.is_some_and(|idle| idle >= idle_threshold);
+        if away == self.away {
+            return None;
+        }
+        self.away = away;
+        Some(if away { Presence::Away } else { Presence::Back })
+    }
+}
+
+/// Samples of the backend every [`ACTIVITY_CHECK_INTERVAL_AS_SEC`], for a subscription.
+///
+/// Connection and sampling run on a blocking thread. Only the first of several errors in a
+/// row is emitted; if the backend cannot be connected the stream stays silent afterwards.
+pub fn samples(
+    backend: &ActivityBackend,
+) -> impl Stream<Item = Result<ActivitySample, ActivityError>> + use<> {
+    let backend = *backend;
+    iced::stream::channel(1, async move |mut output| {
+        let mut source = match blocking::unblock(move || connect(backend)).await {
+            Err(err) => {
+                let _ = output.send(Err(err)).await;
+                return;
+            }
+            Ok(source) => source,
+        };
+
+        let mut failing = false;
+        loop {
+            async_io::Timer::after(Duration::from_secs(ACTIVITY_CHECK_INTERVAL_AS_SEC)).await;
+            let (returned_source, sample) = blocking::unblock(move || {
+                let sample = source.sample();
+                (source, sample)
+            })
+            .await;
+            source = returned_source;
+
+            let was_failing = failing;
+            failing = sample.is_err();
+            if (!failing || !was_failing) && output.send(sample).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::dbus::test_bus::TestBus;
+
+    const THRESHOLD: Duration = Duration::from_mins(5);
+
+    fn idle(minutes: u64) -> ActivitySample {
+        ActivitySample {
+            locked: false,
+            idle_for: Some(Duration::from_mins(minutes)),
+        }
+    }
+
+    #[test]
+    fn away_after_idle_threshold_and_back_on_input() {
+        let mut detector = AwayDetector::default();
+
+        assert_eq!(detector.update(&idle(1), THRESHOLD), None);
+        assert_eq!(detector.update(&idle(5), THRESHOLD), Some(Presence::Away));
+        assert_eq!(detector.update(&idle(20), THRESHOLD), None);
+        assert!(detector.is_away());
+        assert_eq!(detector.update(&idle(0), THRESHOLD), Some(Presence::Back));
+    }
+
+    #[test]
+    fn locked_session_is_away_regardless_of_idle_time() {
+        let mut detector = AwayDetector::default();
+        let locked = ActivitySample {
+            locked: true,
+            idle_for: None,
+        };
+
+        assert_eq!(detector.update(&locked, THRESHOLD), Some(Presence::Away));
+        assert_eq!(detector.update(&locked.merge(idle(0)), THRESHOLD), None);
+        assert_eq!(
+            detector.update(&ActivitySample::default(), THRESHOLD),
+            Some(Presence::Back)
+        );
+    }
+
+    #[test]
+    fn merged_samples_use_latest_input() {
+        assert_eq!(
+            idle(3).merge(idle(7)).merge(ActivitySample::default()),
+            idle(3)
+        );
+    }
+
+    const TEST_SERVICE: &str = "org.rustnot.TestSession";
+
+    /// Test backend standing in for the logind session.
+    struct FakeSession {
+        locked: bool,
+    }
+
+    #[zbus::interface(name = "org.freedesktop.login1.Session")]
+    impl FakeSession {
+        #[zbus(property)]
+        fn locked_hint(&self) -> bool {
+            self.locked
+        }
+    }
+
+    /// Test backend standing in for GNOME's idle monitor.
+    struct FakeIdleMonitor {
+        idle_as_ms: u64,
+    }
+
+    #[zbus::interface(name = "org.gnome.Mutter.IdleMonitor")]
+    impl FakeIdleMonitor {
+        fn get_idletime(&self) -> u64 {
+            self.idle_as_ms
+        }
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn backends_read_session_from_bus() {
+        let bus = TestBus::start();
+        let service = bus.connect(Some(TEST_SERVICE));
+        let object_server = service.object_server();
+        object_server
+            .at(LOGIND_SESSION_PATH, FakeSession { locked: false })
+            .unwrap();
+        object_server
+            .at(MUTTER_PATH, FakeIdleMonitor { idle_as_ms: 0 })
+            .unwrap();
+        let set_idle = |idle_as_ms: u64| {
+            object_server
+                .interface::<_, FakeIdleMonitor>(MUTTER_PATH)
+                .unwrap()
+                .get_mut()
+                .idle_as_ms = idle_as_ms;
+        };
+
+        let client = bus.connect(None);
+        let mut source = CombinedSource(vec![
+            Box::new(LogindSource::with_connection(&client, TEST_SERVICE).unwrap()),
+            Box::new(MutterSource::with_connection(client.clone(), TEST_SERVICE)),
+        ]);
+        let mut detector = AwayDetector::default();
+        let mut presence =
+            |source: &mut CombinedSource| detector.update(&source.sample().unwrap(), THRESHOLD);
+
+        assert_eq!(presence(&mut source), None);
+
+        set_idle(6 * 60 * 1000);
+        assert_eq!(presence(&mut source), Some(Presence::Away));
+        set_idle(200);
+        assert_eq!(presence(&mut source), Some(Presence::Back));
+
+        object_server
+            .interface::<_, FakeSession>(LOGIND_SESSION_PATH)
+            .unwrap()
+            .get_mut()
+            .locked = true;
+        assert_eq!(presence(&mut source), Some(Presence::Away));
+    }
+}
@@ -1,29 +1,64 @@
-use crate::components::{button_with_icon, default_tooltip, icon_button, modal};
-use crate::settings::{Settings, Stance};
-use crate::settings_file::{SETTINGS_FILE_NAME, SettingsFileError};
+use crate::activity::{self, ActivityError, ActivitySample, AwayDetector, Presence};
+use crate::cli::SettingsOverrides;
+use crate::components::{
+    WeekChart, banner, button_with_icon, default_tooltip, error_panel, icon_button, modal,
+};
+use crate::dbus::{self, DBusEvent};
+use crate::history::CycleRecord;
+#[cfg(unix)]
+use crate::ipc::{self, IpcError};
+use crate::notifier::{Notifications, Toast, ToastAction, ToastLevel};
+use crate::remote::{IpcRequest, QUIT_DELAY_AS_MS};
+use crate::settings::{
+    MIN_WINDOW_SIZE, NotifierBackend, RestartPolicy, RunningCyclePolicy, Settings, SettingsField,
+    Stance, ThemeChoice, WakePolicy, WindowGeometry,
+};
+use crate::settings_file::{
+    FileStamp, SETTINGS_FILE_CHECK_INTERVAL_AS_SEC, SettingsFileError,
+    WINDOW_STATE_SAVE_DELAY_AS_SEC, settings_file_path, settings_file_stamp,
+};
+use crate::state::CycleState;
+use crate::stats::{StandingGoal, Statistics};
 use crate::styles;
+use crate::timer::{CycleId, RestoreMode, SavedCycle, SystemClock, Timer, TimerEvent};
 
-use iced::Element;
+use chrono::Local;
 use iced::keyboard::{self, key};
 use iced::time::{self, Duration, Instant, milliseconds};
-use iced::widget::{button, column, container, operation, radio, row, rule, space, text};
-use iced_aw;
-use notify_rust::{Notification, Timeout};
+use iced::widget::{
+    button, canvas, checkbox, column, container, operation, pick_list, progress_bar, radio, row,
+    rule, scrollable, space, text, text_input,
+};
+use iced::{Element, window};
+use rustnot::countdown::{format_countdown, next_cycle_text};
+use rustnot::protocol::{Request, Response, Status};
 
 #[derive(Debug, Default)]
 pub struct App {
-    theme: Option<iced::Theme>,
     settings_modal_show: bool,
+    statistics_show: bool,
+    statistics: Statistics,
+    standing_goal: StandingGoal,
     settings_modal_fields: Settings,
+    profile_name_input: String,
+    profile_name_error: Option<String>,
     settings: Settings,
-    current_timer_cycle: Option<TimerCycleInfo>,
-}
-
-#[derive(Debug, Clone, Copy)]
-struct TimerCycleInfo {
-    start_time: Instant,
-    duration: Duration,
-    stace: Stance,
+    settings_file_error: Option<SettingsFileError>,
+    settings_file_stamp: Option<FileStamp>,
+    /// When the theme or window geometry last changed without being saved yet.
+    window_state_changed_at: Option<Instant>,
+    settings_overrides: SettingsOverrides,
+    timer: Timer,
+    /// Cycle of the last run, waiting for the user to decide how to continue it.
+    pending_saved_cycle: Option<SavedCycle>,
+    away: AwayDetector,
+    /// Whether the timer was paused because the user went away, to resume it on return.
+    auto_paused: bool,
+    /// Whether the last activity sample had no idle time, so only the screen lock counts.
+    idle_time_unavailable: bool,
+    /// Connection of the D-Bus service, once it is available.
+    dbus_connection: Option<zbus::Connection>,
+    notifications: Notifications,
 }
 
 #[derive(Debug, Clone)]
@@ -31,56 +66,232 @@ pub enum Message {
     KeyBoardEvent(keyboard::Event),
     TimerStart,
     TimerStop,
+    TimerPause,
+    TimerResume,
+    TimerReset,
     TimerTick,
+    ActivitySampled(Result<ActivitySample, ActivityError>),
+    #[cfg(unix)]
+    IpcReceived(Result<IpcRequest, IpcError>),
+    DBusEvent(DBusEvent),
     ManualTimerCycleEnd,
-    ThemeChanged(Option<iced::Theme>),
+    StanceChangeDone(CycleId),
+    StanceChangeSnooze(CycleId),
+    StanceChangeSkip(CycleId),
+    SavedCycleContinue,
+    SavedCycleFresh,
+    SavedCycleDiscard,
+    BannerDismiss,
+    SettingsFileErrorDismiss,
+    ThemeChanged(ThemeChoice),
+    WindowMoved(iced::Point),
+    WindowResized(iced::Size),
+    WindowStateSave,
+    WindowCloseRequested,
+    Quit,
+    ProfileSelected(String),
+    StatisticsShow,
+    StatisticsHide,
     SettingsModalShow,
     SettingsModalHide,
     SettingsConfirmAndModalHide,
     SettingsResetToDefault,
+    SettingProfileSelected(String),
+    SettingProfileNameChanged(String),
+    SettingProfileCreate,
+    SettingProfileDelete,
     SettingSitTimeChanged(u64),
     SettingStandTimeChanged(u64),
     SettingStartStanceChanged(Stance),
+    SettingNotifierBackendChanged(NotifierBackend),
+    SettingSnoozeTimeChanged(u64),
+    SettingDailyStandingGoalChanged(u64),
+    SettingRunningCycleOnReloadChanged(RunningCyclePolicy),
+    SettingRunningCycleOnRestartChanged(RestartPolicy),
+    SettingWakePolicyChanged(WakePolicy),
+    SettingConfirmStanceChangeToggled(bool),
+    SettingEscalationToggled(bool),
+    SettingEscalationRepeatTimeChanged(u64),
+    SettingEscalationMaxRepeatIntervalChanged(u64),
+    SettingAlwaysOnTopToggled(bool),
+    SettingAutoPauseToggled(bool),
+    SettingAutoPauseIdleTimeChanged(u64),
     SettingsSaveToFile,
     SettingsLoadFromFile,
+    SettingsRestorePrevious,
+    SettingsFileCheck,
+}
+
+/// Initial settings of the main window.
+pub fn window_settings(geometry: &WindowGeometry) -> window::Settings {
+    window::Settings {
+        size: iced::Size::new(geometry.width as f32, geometry.height as f32),
+        min_size: Some(iced::Size::new(
+            MIN_WINDOW_SIZE as f32,
+            MIN_WINDOW_SIZE as f32,
+        )),
+        position: match geometry.position() {
+            Some((x, y)) => window::Position::Specific(iced::Point::new(x as f32, y as f32)),
+            None => window::Position::Default,
+        },
+        level: window_level(geometry),
+        // The theme and window geometry are saved before exiting.
+        exit_on_close_request: false,
+        ..Default::default()
+    }
+}
+
+fn window_level(geometry: &WindowGeometry) -> window::Level {
+    if geometry.always_on_top {
+        window::Level::AlwaysOnTop
+    } else {
+        window::Level::Normal
+    }
+}
+
+/// Resize, move and restack the main window according to `geometry`.
+fn apply_window_geometry(geometry: WindowGeometry) -> iced::Task<Message> {
+    window::latest().then(move |id| match id {
+        Some(id) => {
+            let mut tasks = vec![
+                window::resize(
+                    id,
+                    iced::Size::new(geometry.width as f32, geometry.height as f32),
+                ),
+                window::set_level(id, window_level(&geometry)),
+            ];
+            if let Some((x, y)) = geometry.position() {
+                tasks.push(window::move_to(id, iced::Point::new(x as f32, y as f32)));
+            }
+            iced::Task::batch(tasks)
+        }
+        None => iced::Task::none(),
+    })
+}
+
+fn raise_window() -> iced::Task<Message> {
+    window::latest().then(|id| match id {
+        Some(id) => iced::Task::batch([
+            window::gain_focus(id),
+            window::request_user_attention(id, Some(window::UserAttention::Critical)),
+        ]),
+        None => iced::Task::none(),
+    })
+}
+
+impl Message {
+    /// Answer to the notification about the stance change, which started cycle `cycle_id`.
+    fn stance_change_answer(action: ToastAction, cycle_id: CycleId) -> Self {
+        match action {
+            ToastAction::Done => Message::StanceChangeDone(cycle_id),
+            ToastAction::Snooze => Message::StanceChangeSnooze(cycle_id),
+            ToastAction::Skip => Message::StanceChangeSkip(cycle_id),
+        }
+    }
 }
 
 impl App {
-    pub fn new() -> Self {
-        let loaded_settings = match Settings::load_from_file() {
-            Err(SettingsFileError::MissingFile) => Settings::default(),
-            Err(err) => {
-                Notification::new()
-                    .summary("Failed loading settings")
-                    .body(&format!(
+    /// Set up the app with the result of loading the settings file.
+    ///
+    /// The overrides from the command line are applied on top of the loaded settings. With
+    /// `headless` set, the window is hidden and toasts meant for the banner are logged.
+    pub fn new(
+        loaded_settings: Result<Settings, SettingsFileError>,
+        settings_overrides: SettingsOverrides,
+        autostart_timer: bool,
+        headless: bool,
+    ) -> (Self, iced::Task<Message>) {
+        let (mut loaded_settings, toast, settings_file_error) = match loaded_settings {
+            Err(SettingsFileError::MissingFile { .. }) => (Settings::default(), None, None),
+            Err(err) => (
+                Settings::default(),
+                Some(Toast::error(
+                    "Failed loading settings",
+                    format!(
                         "An error occured while loading the settings from file: {}",
                         err
-                    ))
-                    .sound_name("dialog-error")
-                    .timeout(Timeout::Milliseconds(10 * 1000))
-                    .show()
-                    .expect("unable to toast");
-                Settings::default()
-            }
-            Ok(s) => {
-                Notification::new()
-                    .summary("Settings loaded")
-                    .body("Successfully loaded settings from file.")
-                    .sound_name("dialog-information")
-                    .timeout(Timeout::Milliseconds(10 * 1000))
-                    .show()
-                    .expect("unable to toast");
-                s
+                    ),
+                )),
+                Some(err),
+            ),
+            Ok(s) => (
+                s,
+                Some(Toast::info(
+                    "Settings loaded",
+                    "Successfully loaded settings from file.",
+                )),
+                None,
+            ),
+        };
+
+        settings_overrides.apply(&mut loaded_settings);
+
+        let mut notifications = Notifications::new(loaded_settings.notifier_backend);
+        notifications.set_headless(headless);
+        if let Some(toast) = toast {
+            notifications.notify(toast);
+        }
+
+        let records = match CycleRecord::load_all_from_file() {
+            Err(err) => {
+                notifications.notify(Toast::error(
+                    "Failed loading history",
+                    format!("An error occured while reading the history file: {}", err),
+                ));
+                Vec::new()
             }
+            Ok(records) => records,
         };
+        let today = Local::now().date_naive();
+        let statistics = Statistics::from_records(&records, today);
+        let standing_goal =
+            StandingGoal::from_records(&records, today, loaded_settings.daily_standing_goal_as_min);
 
-        Self {
-            theme: None,
+        let mut app = Self {
             settings_modal_show: false,
+            statistics_show: false,
+            statistics,
+            standing_goal,
+            settings_modal_fields: loaded_settings.clone(),
+            profile_name_input: loaded_settings.active_profile.clone(),
+            profile_name_error: None,
             settings: loaded_settings,
-            settings_modal_fields: loaded_settings,
-            current_timer_cycle: None,
-        }
+            settings_file_error,
+            settings_file_stamp: settings_file_stamp(),
+            window_state_changed_at: None,
+            settings_overrides,
+            timer: Timer::new(SystemClock),
+            pending_saved_cycle: None,
+            away: AwayDetector::default(),
+            auto_paused: false,
+            idle_time_unavailable: false,
+            dbus_connection: None,
+            notifications,
+        };
+        let restore_task = match CycleState::load_from_file() {
+            Err(err) => {
+                app.notifications.notify(Toast::error(
+                    "Failed restoring cycle",
+                    format!("An error occured while reading the state file: {}", err),
+                ));
+                iced::Task::none()
+            }
+            Ok(None) => iced::Task::none(),
+            Ok(Some(saved)) => match app.settings.running_cycle_on_restart {
+                RestartPolicy::Continue => app.restore_cycle(&saved, RestoreMode::Continue),
+                RestartPolicy::Fresh => app.restore_cycle(&saved, RestoreMode::Fresh),
+                RestartPolicy::Ask => {
+                    app.pending_saved_cycle = Some(saved);
+                    iced::Task::none()
+                }
+            },
+        };
+        let start_task = if autostart_timer {
+            iced::Task::done(Message::TimerStart)
+        } else {
+            iced::Task::none()
+        };
+        (app, iced::Task::batch([restore_task, start_task]))
     }
 
     pub fn update(&mut self, message: Message) -> iced::Task<Message> {
@@ -93,33 +304,218 @@ pub fn update(&mut self, message: Message) -> iced::Task<Message> {
                     if self.settings_modal_show {
                         self.hide_modal();
                     }
+                    self.statistics_show = false;
                     iced::Task::none()
                 }
                 _ => iced::Task::none(),
             },
             Message::TimerStart => {
-                self.start_new_cycle();
-                iced::Task::none()
+                if let Err(errors) = self.settings.validate() {
+                    self.notifications.notify(Toast::error(
+                        "Invalid settings",
+                        format!(
+                            "The timer cannot start with invalid settings:\n{}",
+                            errors
+                                .iter()
+                                .map(ToString::to_string)
+                                .collect::<Vec<_>>()
+                                .join("\n")
+                        ),
+                    ));
+                    return iced::Task::none();
+                }
+                self.pending_saved_cycle = None;
+                let event = self.timer.start(&self.settings);
+                self.handle_timer_event(event)
             }
             Message::TimerStop => {
-                self.current_timer_cycle = None;
-                iced::Task::none()
+                self.auto_paused = false;
+                let event = self.timer.stop();
+                self.handle_timer_event(event)
+            }
+            Message::TimerPause => {
+                let event = self.timer.pause();
+                self.handle_timer_event(event)
+            }
+            Message::TimerResume => {
+                self.auto_paused = false;
+                let event = self.timer.resume();
+                self.handle_timer_event(event)
+            }
+            Message::TimerReset => {
+                let event = self.timer.reset();
+                self.handle_timer_event(event)
             }
             Message::TimerTick => {
-                if let Some(cycle_info) = &self.current_timer_cycle {
-                    let run_duration = Instant::now() - cycle_info.start_time;
-                    if run_duration >= cycle_info.duration {
-                        self.start_new_cycle();
-                    };
+                let event = self.timer.tick(&self.settings);
+                self.handle_timer_event(event)
+            }
+            Message::ActivitySampled(sample) => {
+                let sample = match sample {
+                    Err(err) => {
+                        self.notifications.notify(Toast::error(
+                            "Auto-pause unavailable",
+                            format!("Cannot tell whether you are away: {}", err),
+                        ));
+                        return iced::Task::none();
+                    }
+                    Ok(sample) => sample,
                 };
-                iced::Task::none()
+                let idle_time_unavailable = sample.idle_for.is_none();
+                if idle_time_unavailable && !self.idle_time_unavailable {
+                    self.notifications.notify(Toast::info(
+                        "Idle time unavailable",
+                        "No source for the time since your last input, \
+                        only locking the screen pauses the timer.",
+                    ));
+                }
+                self.idle_time_unavailable = idle_time_unavailable;
+                let idle_threshold =
+                    Duration::from_mins(self.settings.auto_pause.idle_threshold_as_min);
+                match self.away.update(&sample, idle_threshold) {
+                    Some(Presence::Away) => {
+                        let event = self.timer.pause();
+                        self.auto_paused = event.is_some();
+                        self.handle_timer_event(event)
+                    }
+                    Some(Presence::Back) if self.auto_paused => {
+                        self.auto_paused = false;
+                        let event = self.timer.resume();
+                        self.notifications.notify(Toast::info(
+                            "Welcome back",
+                            "The timer was paused while you were away and runs again.",
+                        ));
+                        self.handle_timer_event(event)
+                    }
+                    _ => iced::Task::none(),
+                }
             }
+            #[cfg(unix)]
+            Message::IpcReceived(ipc_request) => {
+                let ipc_request = match ipc_request {
+                    Err(err) => {
+                        // E.g. a second instance, nothing the user needs to act on.
+                        eprintln!("[rustnot] Remote control unavailable: {}", err);
+                        return iced::Task::none();
+                    }
+                    Ok(ipc_request) => ipc_request,
+                };
+                self.answer_ipc_request(ipc_request)
+            }
+            Message::DBusEvent(event) => match event {
+                DBusEvent::Connected(connection) => {
+                    self.dbus_connection = Some(connection);
+                    iced::Task::none()
+                }
+                DBusEvent::Request(ipc_request) => self.answer_ipc_request(ipc_request),
+                DBusEvent::Failed(err) => {
+                    // E.g. no session bus or a second instance, nothing the user needs to
+                    // act on.
+                    eprintln!(
+                        "[rustnot] D-Bus service unavailable: failed to provide {} on the session bus: {}",
+                        dbus::BUS_NAME,
+                        err
+                    );
+                    iced::Task::none()
+                }
+            },
             Message::ManualTimerCycleEnd => {
-                self.start_new_cycle();
+                let event = self.timer.skip(&self.settings);
+                self.handle_timer_event(event)
+            }
+            // Answers to notifications of earlier cycles are outdated.
+            Message::StanceChangeDone(cycle_id)
+            | Message::StanceChangeSnooze(cycle_id)
+            | Message::StanceChangeSkip(cycle_id)
+                if !self.timer.is_current_cycle(cycle_id) =>
+            {
+                iced::Task::none()
+            }
+            Message::StanceChangeDone(_) => {
+                let event = self.timer.acknowledge();
+                self.handle_timer_event(event)
+            }
+            Message::StanceChangeSnooze(_) => {
+                let event = self
+                    .timer
+                    .snooze(Duration::from_mins(self.settings.snooze_duration_as_min));
+                self.handle_timer_event(event)
+            }
+            Message::StanceChangeSkip(_) => {
+                let event = self.timer.skip(&self.settings);
+                self.handle_timer_event(event)
+            }
+            Message::SavedCycleContinue => match self.pending_saved_cycle.take() {
+                Some(saved) => self.restore_cycle(&saved, RestoreMode::Continue),
+                None => iced::Task::none(),
+            },
+            Message::SavedCycleFresh => match self.pending_saved_cycle.take() {
+                Some(saved) => self.restore_cycle(&saved, RestoreMode::Fresh),
+                None => iced::Task::none(),
+            },
+            Message::SavedCycleDiscard => {
+                self.pending_saved_cycle = None;
+                self.save_cycle_state();
+                iced::Task::none()
+            }
+            Message::BannerDismiss => {
+                self.notifications.dismiss_banner();
+                iced::Task::none()
+            }
+            Message::SettingsFileErrorDismiss => {
+                self.settings_file_error = None;
                 iced::Task::none()
             }
             Message::ThemeChanged(new_theme) => {
-                self.theme = new_theme;
+                self.settings.theme = new_theme;
+                self.settings_modal_fields.theme = new_theme;
+                self.window_state_changed_at = Some(Instant::now());
+                iced::Task::none()
+            }
+            Message::WindowMoved(position) => {
+                self.settings.window.x = Some(position.x.round() as i32);
+                self.settings.window.y = Some(position.y.round() as i32);
+                self.settings_modal_fields.window.x = self.settings.window.x;
+                self.settings_modal_fields.window.y = self.settings.window.y;
+                self.window_state_changed_at = Some(Instant::now());
+                iced::Task::none()
+            }
+            Message::WindowResized(size) => {
+                // Window managers may ignore the minimum size, e.g. for tiled windows.
+                self.settings.window.width = (size.width.round() as u32).max(MIN_WINDOW_SIZE);
+                self.settings.window.height = (size.height.round() as u32).max(MIN_WINDOW_SIZE);
+                self.settings_modal_fields.window.width = self.settings.window.width;
+                self.settings_modal_fields.window.height = self.settings.window.height;
+                self.window_state_changed_at = Some(Instant::now());
+                iced::Task::none()
+            }
+            Message::WindowStateSave => {
+                let delay = Duration::from_secs(WINDOW_STATE_SAVE_DELAY_AS_SEC);
+                if self
+                    .window_state_changed_at
+                    .is_some_and(|changed_at| changed_at.elapsed() >= delay)
+                {
+                    self.save_window_state();
+                }
+                iced::Task::none()
+            }
+            Message::WindowCloseRequested | Message::Quit => {
+                if self.window_state_changed_at.is_some() {
+                    self.save_window_state();
+                }
+                iced::exit()
+            }
+            Message::ProfileSelected(name) => {
+                self.settings.select_profile(&name);
+                iced::Task::none()
+            }
+            Message::StatisticsShow => {
+                self.statistics_show = true;
+                self.reload_statistics();
+                iced::Task::none()
+            }
+            Message::StatisticsHide => {
+                self.statistics_show = false;
                 iced::Task::none()
             }
             Message::SettingsModalShow => {
@@ -131,77 +527,269 @@ pub fn update(&mut self, message: Message) -> iced::Task<Message> {
                 self.hide_modal();
                 iced::Task::none()
             }
+            Message::SettingProfileSelected(name) => {
+                self.settings_modal_fields.select_profile(&name);
+                self.reset_profile_name_input();
+                iced::Task::none()
+            }
+            Message::SettingProfileNameChanged(new_name) => {
+                self.profile_name_error =
+                    self.settings_modal_fields.rename_profile(&new_name).err();
+                self.profile_name_input = new_name;
+                iced::Task::none()
+            }
+            Message::SettingProfileCreate => {
+                self.settings_modal_fields.create_profile();
+                self.reset_profile_name_input();
+                iced::Task::none()
+            }
+            Message::SettingProfileDelete => {
+                self.settings_modal_fields.delete_profile();
+                self.reset_profile_name_input();
+                iced::Task::none()
+            }
             Message::SettingSitTimeChanged(new_sit_time) => {
-                self.settings_modal_fields.sit_duration_as_min = new_sit_time;
+                self.settings_modal_fields.profile_mut().sit_duration_as_min = new_sit_time;
                 iced::Task::none()
             }
             Message::SettingStandTimeChanged(new_stand_time) => {
-                self.settings_modal_fields.stand_duration_as_min = new_stand_time;
+                self.settings_modal_fields
+                    .profile_mut()
+                    .stand_duration_as_min = new_stand_time;
                 iced::Task::none()
             }
             Message::SettingStartStanceChanged(new_start_stance) => {
-                self.settings_modal_fields.start_stance = new_start_stance;
+                self.settings_modal_fields.profile_mut().start_stance = new_start_stance;
+                iced::Task::none()
+            }
+            Message::SettingNotifierBackendChanged(new_backend) => {
+                self.settings_modal_fields.notifier_backend = new_backend;
+                iced::Task::none()
+            }
+            Message::SettingSnoozeTimeChanged(new_snooze_time) => {
+                self.settings_modal_fields.snooze_duration_as_min = new_snooze_time;
+                iced::Task::none()
+            }
+            Message::SettingDailyStandingGoalChanged(new_goal) => {
+                self.settings_modal_fields.daily_standing_goal_as_min = new_goal;
+                iced::Task::none()
+            }
+            Message::SettingRunningCycleOnReloadChanged(new_policy) => {
+                self.settings_modal_fields.running_cycle_on_reload = new_policy;
+                iced::Task::none()
+            }
+            Message::SettingRunningCycleOnRestartChanged(new_policy) => {
+                self.settings_modal_fields.running_cycle_on_restart = new_policy;
+                iced::Task::none()
+            }
+            Message::SettingWakePolicyChanged(new_policy) => {
+                self.settings_modal_fields.wake_policy = new_policy;
+                iced::Task::none()
+            }
+            Message::SettingConfirmStanceChangeToggled(confirm_stance_change) => {
+                self.settings_modal_fields.confirm_stance_change = confirm_stance_change;
+                iced::Task::none()
+            }
+            Message::SettingEscalationToggled(enabled) => {
+                self.settings_modal_fields.escalation.enabled = enabled;
+                iced::Task::none()
+            }
+            Message::SettingEscalationRepeatTimeChanged(new_repeat_time) => {
+                self.settings_modal_fields.escalation.repeat_after_as_min = new_repeat_time;
+                iced::Task::none()
+            }
+            Message::SettingEscalationMaxRepeatIntervalChanged(new_interval) => {
+                self.settings_modal_fields
+                    .escalation
+                    .max_repeat_interval_as_min = new_interval;
+                iced::Task::none()
+            }
+            Message::SettingAlwaysOnTopToggled(always_on_top) => {
+                self.settings_modal_fields.window.always_on_top = always_on_top;
+                iced::Task::none()
+            }
+            Message::SettingAutoPauseToggled(enabled) => {
+                self.settings_modal_fields.auto_pause.enabled = enabled;
+                iced::Task::none()
+            }
+            Message::SettingAutoPauseIdleTimeChanged(new_idle_time) => {
+                self.settings_modal_fields.auto_pause.idle_threshold_as_min = new_idle_time;
                 iced::Task::none()
             }
             Message::SettingsConfirmAndModalHide => {
-                self.settings.sit_duration_as_min = self.settings_modal_fields.sit_duration_as_min;
-                self.settings.stand_duration_as_min =
-                    self.settings_modal_fields.stand_duration_as_min;
-                self.settings.start_stance = self.settings_modal_fields.start_stance;
+                if self.settings_modal_fields.validate().is_err()
+                    || self.profile_name_error.is_some()
+                {
+                    return iced::Task::none();
+                }
+                self.settings.active_profile = self.settings_modal_fields.active_profile.clone();
+                self.settings.profiles = self.settings_modal_fields.profiles.clone();
+                self.settings.notifier_backend = self.settings_modal_fields.notifier_backend;
+                self.settings.snooze_duration_as_min =
+                    self.settings_modal_fields.snooze_duration_as_min;
+                self.settings.confirm_stance_change =
+                    self.settings_modal_fields.confirm_stance_change;
+                self.settings.escalation = self.settings_modal_fields.escalation;
+                self.settings.daily_standing_goal_as_min =
+                    self.settings_modal_fields.daily_standing_goal_as_min;
+                self.settings.running_cycle_on_reload =
+                    self.settings_modal_fields.running_cycle_on_reload;
+                self.settings.running_cycle_on_restart =
+                    self.settings_modal_fields.running_cycle_on_restart;
+                self.settings.wake_policy = self.settings_modal_fields.wake_policy;
+                if self.settings.auto_pause.backend != self.settings_modal_fields.auto_pause.backend
+                {
+                    // The new backend reports its idle time with its first sample.
+                    self.idle_time_unavailable = false;
+                }
+                self.settings.auto_pause = self.settings_modal_fields.auto_pause;
+                let level_changed = self.settings.window.always_on_top
+                    != self.settings_modal_fields.window.always_on_top;
+                self.settings.window.always_on_top =
+                    self.settings_modal_fields.window.always_on_top;
+                self.notifications
+                    .set_backend(self.settings.notifier_backend);
                 self.hide_modal();
-                iced::Task::none()
+                if level_changed {
+                    let level = window_level(&self.settings.window);
+                    window::latest().then(move |id| match id {
+                        Some(id) => window::set_level(id, level),
+                        None => iced::Task::none(),
+                    })
+                } else {
+                    iced::Task::none()
+                }
             }
             Message::SettingsResetToDefault => {
                 self.settings_modal_fields = Settings::default();
+                self.reset_profile_name_input();
                 iced::Task::none()
             }
-            Message::SettingsSaveToFile => {
-                match self.settings_modal_fields.save_to_file() {
+            Message::SettingsSaveToFile => match self.settings_modal_fields.save_to_file() {
+                Err(err) => {
+                    self.notifications.notify(Toast::error(
+                        "Failed saving settings",
+                        format!(
+                            "An error occured while saving the settings to file: {}",
+                            err
+                        ),
+                    ));
+                    self.settings_file_error = Some(err);
+                    iced::Task::none()
+                }
+                Ok(_) => {
+                    self.settings_file_error = None;
+                    self.settings_file_stamp = settings_file_stamp();
+                    iced::Task::none()
+                }
+            },
+            Message::SettingsLoadFromFile => {
+                self.settings_modal_fields = match Settings::load_from_file() {
                     Err(err) => {
-                        Notification::new()
-                            .summary("Failed saving settings")
-                            .body(&format!(
-                                "An error occured while saving the settings to file: {}",
+                        self.notifications.notify(Toast::error(
+                            "Failed loading settings",
+                            format!(
+                                "An error occured while loading the settings from file: {}",
                                 err
-                            ))
-                            .sound_name("dialog-error")
-                            .timeout(Timeout::Milliseconds(10 * 1000))
-                            .show()
-                            .expect("unable to toast");
+                            ),
+                        ));
+                        self.settings_file_error = Some(err);
                         return iced::Task::none();
                     }
-                    Ok(_) => return iced::Task::none(),
+                    Ok(s) => s,
                 };
+                self.reset_profile_name_input();
+                self.settings_file_error = None;
+                self.settings_file_stamp = settings_file_stamp();
+                iced::Task::none()
             }
-            Message::SettingsLoadFromFile => {
-                self.settings_modal_fields = match Settings::load_from_file() {
+            Message::SettingsFileCheck => {
+                let stamp = settings_file_stamp();
+                if stamp.is_none() || stamp == self.settings_file_stamp {
+                    return iced::Task::none();
+                }
+                self.settings_file_stamp = stamp;
+                self.reload_settings_file()
+            }
+            Message::SettingsRestorePrevious => {
+                self.settings_modal_fields = match Settings::restore_previous_file() {
                     Err(err) => {
-                        Notification::new()
-                            .summary("Failed loading settings")
-                            .body(&format!(
-                                "An error occured while loading the settings from file: {}",
+                        self.notifications.notify(Toast::error(
+                            "Failed restoring settings",
+                            format!(
+                                "An error occured while restoring the previous settings file: {}",
                                 err
-                            ))
-                            .sound_name("dialog-error")
-                            .timeout(Timeout::Milliseconds(10 * 1000))
-                            .show()
-                            .expect("unable to toast");
+                            ),
+                        ));
+                        self.settings_file_error = Some(err);
                         return iced::Task::none();
                     }
                     Ok(s) => s,
                 };
+                self.reset_profile_name_input();
+                self.settings_file_error = None;
+                self.settings_file_stamp = settings_file_stamp();
+                self.notifications.notify(Toast::info(
+                    "Settings restored",
+                    "Restored the settings file from before the last save.",
+                ));
                 iced::Task::none()
             }
         }
     }
 
     pub fn subscription(&self) -> iced::Subscription<Message> {
-        let tick = match self.current_timer_cycle {
-            None => iced::Subscription::none(),
-            Some(_) => time::every(milliseconds(100)).map(|_| Message::TimerTick),
+        // While away, an unconfirmed stance change cannot be paused, but needs no reminders.
+        let tick = if self.timer.is_running()
+            && !self.timer.is_paused()
+            && !(self.away.is_away() && self.timer.is_awaiting_confirmation())
+        {
+            time::every(milliseconds(100)).map(|_| Message::TimerTick)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let activity = if self.settings.auto_pause.enabled {
+            iced::Subscription::run_with(self.settings.auto_pause.backend, activity::samples)
+                .map(Message::ActivitySampled)
+        } else {
+            iced::Subscription::none()
+        };
+
+        let settings_file_check =
+            time::every(Duration::from_secs(SETTINGS_FILE_CHECK_INTERVAL_AS_SEC))
+                .map(|_| Message::SettingsFileCheck);
+
+        let window_state_save = if self.window_state_changed_at.is_some() {
+            time::every(Duration::from_secs(WINDOW_STATE_SAVE_DELAY_AS_SEC))
+                .map(|_| Message::WindowStateSave)
+        } else {
+            iced::Subscription::none()
         };
 
-        iced::Subscription::batch(vec![tick, keyboard::listen().map(Message::KeyBoardEvent)])
+        let window_geometry = window::events().filter_map(|(_, event)| match event {
+            window::Event::Moved(position) => Some(Message::WindowMoved(position)),
+            window::Event::Resized(size) => Some(Message::WindowResized(size)),
+            window::Event::CloseRequested => Some(Message::WindowCloseRequested),
+            _ => None,
+        });
+
+        // The control socket is a Unix domain socket.
+        #[cfg(unix)]
+        let ipc_requests = iced::Subscription::run(ipc::requests).map(Message::IpcReceived);
+        #[cfg(not(unix))]
+        let ipc_requests = iced::Subscription::none();
+
+        iced::Subscription::batch(vec![
+            tick,
+            activity,
+            ipc_requests,
+            iced::Subscription::run(dbus::events).map(Message::DBusEvent),
+            settings_file_check,
+            window_state_save,
+            window_geometry,
+            keyboard::listen().map(Message::KeyBoardEvent),
+        ])
     }
 
     pub fn view(&self) -> iced::Element<'_, Message> {
@@ -210,26 +798,43 @@ pub fn view(&self) -> iced::Element<'_, Message> {
             .align_x(iced::Alignment::Start)
             .size(styles::TEXT_SIZE_HEADING);
 
-        let theme_toggle_btn = match self.theme {
-            Some(iced::Theme::Dark) => icon_button(concat!(
+        let theme_toggle_btn = match self.settings.theme {
+            ThemeChoice::Dark => icon_button(concat!(
                 env!("CARGO_MANIFEST_DIR"),
                 "/resources/images/sun.svg"
             ))
-            .on_press(Message::ThemeChanged(Some(iced::Theme::Light))),
+            .on_press(Message::ThemeChanged(ThemeChoice::Light)),
 
-            Some(iced::Theme::Light) => icon_button(concat!(
+            ThemeChoice::Light => icon_button(concat!(
                 env!("CARGO_MANIFEST_DIR"),
                 "/resources/images/sun-moon.svg"
             ))
-            .on_press(Message::ThemeChanged(None)),
+            .on_press(Message::ThemeChanged(ThemeChoice::System)),
 
-            None | _ => icon_button(concat!(
+            ThemeChoice::System => icon_button(concat!(
                 env!("CARGO_MANIFEST_DIR"),
                 "/resources/images/moon.svg"
             ))
-            .on_press(Message::ThemeChanged(Some(iced::Theme::Dark))),
+            .on_press(Message::ThemeChanged(ThemeChoice::Dark)),
         };
 
+        let statistics_btn = default_tooltip(
+            icon_button(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/resources/images/chart-column.svg"
+            ))
+            .on_press(Message::StatisticsShow),
+            "Statistics",
+        );
+
+        let profile_picker = pick_list(
+            self.settings.profile_names(),
+            Some(self.settings.active_profile.clone()),
+            Message::ProfileSelected,
+        )
+        .width(styles::PROFILE_PICK_LIST_WIDTH)
+        .text_size(styles::TEXT_SIZE_SMALL);
+
         let settings_btn = icon_button(concat!(
             env!("CARGO_MANIFEST_DIR"),
             "/resources/images/settings.svg"
@@ -241,7 +846,7 @@ pub fn view(&self) -> iced::Element<'_, Message> {
                 .width(iced::Length::Fill)
                 .align_x(iced::Alignment::Start)
                 .size(styles::TEXT_SIZE_NORMAL),
-            text!("{}", &self.settings.sit_duration_as_min)
+            text!("{}", self.settings.profile().sit_duration_as_min)
                 .align_x(iced::Alignment::End)
                 .size(styles::TEXT_SIZE_NORMAL),
         ];
@@ -251,7 +856,7 @@ pub fn view(&self) -> iced::Element<'_, Message> {
                 .width(iced::Length::Fill)
                 .align_x(iced::Alignment::Start)
                 .size(styles::TEXT_SIZE_NORMAL),
-            text!("{}", &self.settings.stand_duration_as_min)
+            text!("{}", self.settings.profile().stand_duration_as_min)
                 .align_x(iced::Alignment::End)
                 .size(styles::TEXT_SIZE_NORMAL),
         ];
@@ -262,10 +867,10 @@ pub fn view(&self) -> iced::Element<'_, Message> {
                 .align_x(iced::Alignment::Start)
                 .size(styles::TEXT_SIZE_NORMAL),
             text(
-                match if let Some(current_cycle) = &self.current_timer_cycle {
-                    &current_cycle.stace
+                match if let Some(current_cycle) = self.timer.current_cycle() {
+                    current_cycle.stance
                 } else {
-                    &self.settings.start_stance
+                    self.settings.profile().start_stance
                 } {
                     Stance::Sitting => "Sitting",
                     Stance::Standing => "Standing",
@@ -280,39 +885,84 @@ pub fn view(&self) -> iced::Element<'_, Message> {
                 .width(iced::Length::Fill)
                 .align_x(iced::Alignment::Start)
                 .size(styles::TEXT_SIZE_NORMAL),
-            text(match &self.current_timer_cycle {
-                Some(cycle_info) => {
-                    const MINUTE: u64 = 60;
-                    const HOUR: u64 = 60 * MINUTE;
-
-                    let run_duration = Instant::now() - cycle_info.start_time;
-                    let displayed_duration_as_sec = if run_duration < cycle_info.duration {
-                        (cycle_info.duration - run_duration).as_secs()
-                    } else {
-                        0
-                    };
-                    format!(
-                        "{:0>2}:{:0>2}:{:0>2}",
-                        displayed_duration_as_sec / HOUR,
-                        (displayed_duration_as_sec % HOUR) / MINUTE,
-                        displayed_duration_as_sec % MINUTE,
-                    )
-                }
-                None => "-".to_string(),
-            })
+            text(next_cycle_text(
+                self.timer.remaining(),
+                self.timer.is_paused(),
+                self.timer.is_awaiting_confirmation()
+            ))
             .align_x(iced::Alignment::End)
             .size(styles::TEXT_SIZE_NORMAL)
         ];
 
+        let standing_goal_info = (self.settings.daily_standing_goal_as_min > 0).then(|| {
+            let standing_as_min = self
+                .standing_goal
+                .standing_as_sec(self.running_standing_as_sec())
+                / 60;
+            row![
+                text("Standing today:")
+                    .width(iced::Length::Fill)
+                    .align_x(iced::Alignment::Start)
+                    .size(styles::TEXT_SIZE_NORMAL),
+                progress_bar(
+                    0.0..=self.settings.daily_standing_goal_as_min as f32,
+                    standing_as_min as f32
+                )
+                .length(styles::PROGRESS_BAR_LENGTH)
+                .girth(styles::PROGRESS_BAR_GIRTH),
+                text!(
+                    "{}/{}",
+                    standing_as_min,
+                    self.settings.daily_standing_goal_as_min
+                )
+                .align_x(iced::Alignment::End)
+                .size(styles::TEXT_SIZE_NORMAL),
+            ]
+            .spacing(styles::ROW_SPACING)
+            .align_y(iced::Alignment::Center)
+        });
+
+        // The running cycle is only in the history once it ends.
+        let running_reminders = self
+            .timer
+            .current_cycle()
+            .map_or(0, |cycle_info| cycle_info.reminders_sent);
+        let (reminders_today, ignored_stance_changes_today) =
+            if self.statistics.date == Local::now().date_naive() {
+                (
+                    self.statistics.today.reminders + running_reminders,
+                    self.statistics.today.ignored_stance_changes + u32::from(running_reminders > 0),
+                )
+            } else {
+                (running_reminders, u32::from(running_reminders > 0))
+            };
+        let ignored_reminders_info = (reminders_today > 0).then(|| {
+            row![
+                text("Ignored reminders today:")
+                    .width(iced::Length::Fill)
+                    .align_x(iced::Alignment::Start)
+                    .size(styles::TEXT_SIZE_NORMAL),
+                text!(
+                    "{} ({} changes)",
+                    reminders_today,
+                    ignored_stance_changes_today
+                )
+                .align_x(iced::Alignment::End)
+                .size(styles::TEXT_SIZE_NORMAL),
+            ]
+        });
+
         let info_texts = column![
             sit_duration,
             stand_duration,
             current_stance_info,
             next_stance_switch_info,
+            standing_goal_info,
+            ignored_reminders_info,
         ]
         .spacing(styles::COL_SPACING);
 
-        let timer_control_btn = (match &self.current_timer_cycle {
+        let timer_control_btn = (match self.timer.current_cycle() {
             None => button_with_icon(
                 "Start timer",
                 concat!(env!("CARGO_MANIFEST_DIR"), "/resources/images/play.svg"),
@@ -320,43 +970,95 @@ pub fn view(&self) -> iced::Element<'_, Message> {
             .on_press(Message::TimerStart),
             Some(_) => button_with_icon(
                 "Stop timer",
-                concat!(env!("CARGO_MANIFEST_DIR"), "/resources/images/pause.svg"),
+                concat!(env!("CARGO_MANIFEST_DIR"), "/resources/images/square.svg"),
             )
             .style(button::danger)
             .on_press(Message::TimerStop),
         })
         .width(128);
 
-        let stance_switch_btn = button_with_icon(
-            "Skip cycle",
-            concat!(
-                env!("CARGO_MANIFEST_DIR"),
-                "/resources/images/fast-forward.svg"
+        let timer_pause_btn = match self.timer.current_cycle() {
+            Some(cycle_info) if cycle_info.is_paused() => default_tooltip(
+                icon_button(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/resources/images/play.svg"
+                ))
+                .on_press(Message::TimerResume),
+                "Resume timer",
             ),
-        )
-        .on_press_maybe(if self.current_timer_cycle.is_some() {
-            Some(Message::ManualTimerCycleEnd)
+            _ => default_tooltip(
+                icon_button(concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/resources/images/pause.svg"
+                ))
+                .on_press_maybe(self.timer.is_running().then_some(Message::TimerPause)),
+                "Pause timer",
+            ),
+        };
+
+        let timer_reset_btn = default_tooltip(
+            icon_button(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/resources/images/rotate-ccw.svg"
+            ))
+            .on_press_maybe(self.timer.is_running().then_some(Message::TimerReset)),
+            "Reset current cycle",
+        );
+
+        let stance_switch_btn = if let Some(cycle_info) = self
+            .timer
+            .current_cycle()
+            .filter(|cycle_info| cycle_info.is_awaiting_confirmation())
+        {
+            button_with_icon(
+                "Confirm",
+                concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/resources/images/circle-check.svg"
+                ),
+            )
+            .style(button::success)
+            .on_press(Message::StanceChangeDone(cycle_info.id))
         } else {
-            None
-        });
+            button_with_icon(
+                "Skip cycle",
+                concat!(
+                    env!("CARGO_MANIFEST_DIR"),
+                    "/resources/images/fast-forward.svg"
+                ),
+            )
+            .on_press_maybe(if self.timer.is_running() {
+                Some(Message::ManualTimerCycleEnd)
+            } else {
+                None
+            })
+        };
 
         let main_content: Element<'_, Message> = column![
             row![
                 main_heading,
                 space::horizontal(),
+                profile_picker,
+                statistics_btn,
                 theme_toggle_btn,
                 settings_btn
             ]
             .spacing(styles::ROW_SPACING)
-            .align_y(iced::Alignment::Start),
+            .align_y(iced::Alignment::Center),
             rule::horizontal(styles::HORIZONTAL_RULE_HEIGHT),
             info_texts,
             rule::horizontal(styles::HORIZONTAL_RULE_HEIGHT),
-            row![timer_control_btn, space::horizontal(), stance_switch_btn]
-                .width(iced::Length::Fill)
-                .padding(styles::ROW_PADDING)
-                .spacing(styles::ROW_SPACING)
-                .align_y(iced::Alignment::Center),
+            row![
+                timer_control_btn,
+                timer_pause_btn,
+                timer_reset_btn,
+                space::horizontal(),
+                stance_switch_btn
+            ]
+            .width(iced::Length::Fill)
+            .padding([styles::ROW_PADDING, 0])
+            .spacing(styles::ROW_SPACING)
+            .align_y(iced::Alignment::Center),
         ]
         .padding(styles::OUTER_PADDING)
         .spacing(styles::MAIN_COLUMN_SPACING)
@@ -364,63 +1066,424 @@ pub fn view(&self) -> iced::Element<'_, Message> {
         .height(400)
         .into();
 
+        let main_content = match (&self.pending_saved_cycle, self.notifications.banner()) {
+            (Some(saved), _) => banner(
+                main_content,
+                "Continue the last cycle?",
+                format!(
+                    "The timer was running when rustnot was closed, {} for {} of {} min.",
+                    match saved.stance {
+                        Stance::Sitting => "sitting",
+                        Stance::Standing => "standing",
+                    },
+                    format_countdown(saved.elapsed),
+                    saved.duration.as_secs() / 60
+                ),
+                false,
+                vec![
+                    ("Continue", Message::SavedCycleContinue),
+                    ("Start fresh", Message::SavedCycleFresh),
+                ],
+                Message::SavedCycleDiscard,
+            ),
+            (None, Some(toast)) => banner(
+                main_content,
+                &toast.summary,
+                &toast.body,
+                toast.level != ToastLevel::Info,
+                toast
+                    .actions
+                    .iter()
+                    .filter_map(|(action, label)| {
+                        toast.cycle_id.map(|cycle_id| {
+                            (
+                                label.as_str(),
+                                Message::stance_change_answer(*action, cycle_id),
+                            )
+                        })
+                    })
+                    .collect(),
+                Message::BannerDismiss,
+            ),
+            (None, None) => main_content,
+        };
+
+        let main_content = match &self.settings_file_error {
+            Some(err) => error_panel(
+                main_content,
+                "Settings file error",
+                err.details(),
+                Message::SettingsFileErrorDismiss,
+            ),
+            None => main_content,
+        };
+
         if self.settings_modal_show {
+            let modal_errors = self
+                .settings_modal_fields
+                .validate()
+                .err()
+                .unwrap_or_default();
+            let active_profile = &self.settings_modal_fields.active_profile;
+            let field_error = |field: SettingsField| {
+                modal_errors
+                    .iter()
+                    .find(|err| {
+                        err.field == field
+                            && err
+                                .profile
+                                .as_ref()
+                                .is_none_or(|profile| profile == active_profile)
+                    })
+                    .map(|err| {
+                        text!("{}", err.message)
+                            .size(styles::TEXT_SIZE_SMALL)
+                            .style(text::danger)
+                    })
+            };
+
+            let idle_time_warning =
+                (self.settings.auto_pause.enabled && self.idle_time_unavailable).then(|| {
+                    text("No idle time source available, only locking the screen counts as away.")
+                        .size(styles::TEXT_SIZE_SMALL)
+                        .style(text::danger)
+                });
+
+            // The window size has no input, so its errors name the key.
+            let window_size_errors = column(
+                modal_errors
+                    .iter()
+                    .filter(|err| {
+                        matches!(
+                            err.field,
+                            SettingsField::WindowWidth | SettingsField::WindowHeight
+                        )
+                    })
+                    .map(|err| {
+                        text!("{}", err)
+                            .size(styles::TEXT_SIZE_SMALL)
+                            .style(text::danger)
+                            .into()
+                    }),
+            );
+
             let modal_content: Element<'_, Message> = container(
                 column![
                     text("Settings").size(styles::TEXT_SIZE_HEADING),
                     rule::horizontal(styles::HORIZONTAL_RULE_HEIGHT),
-                    column![
-                        row![
-                            text("Sit time [min]:").size(styles::TEXT_SIZE_NORMAL),
-                            space::horizontal(),
-                            iced_aw::number_input(
-                                &self.settings_modal_fields.sit_duration_as_min,
-                                0..=1440,
-                                Message::SettingSitTimeChanged
-                            )
-                            .width(75)
-                            .step(1)
-                            .on_input(Message::SettingSitTimeChanged)
-                            .on_submit(Message::SettingsConfirmAndModalHide),
-                        ]
-                        .align_y(iced::Alignment::Center),
-                        row![
-                            text("Stand time [min]:").size(styles::TEXT_SIZE_NORMAL),
-                            space::horizontal(),
-                            iced_aw::number_input(
-                                &self.settings_modal_fields.stand_duration_as_min,
-                                0..=1440,
-                                Message::SettingStandTimeChanged
-                            )
-                            .width(75)
-                            .step(1)
-                            .on_input(Message::SettingStandTimeChanged)
-                            .on_submit(Message::SettingsConfirmAndModalHide),
-                        ]
-                        .align_y(iced::Alignment::Center),
+                    scrollable(
                         column![
-                            text("Start stance:").size(styles::TEXT_SIZE_NORMAL),
                             row![
-                                radio(
-                                    "Sitting",
-                                    Stance::Sitting,
-                                    Some(self.settings_modal_fields.start_stance),
-                                    Message::SettingStartStanceChanged
+                                text("Profile:").size(styles::TEXT_SIZE_NORMAL),
+                                space::horizontal(),
+                                pick_list(
+                                    self.settings_modal_fields.profile_names(),
+                                    Some(self.settings_modal_fields.active_profile.clone()),
+                                    Message::SettingProfileSelected
                                 )
-                                .size(styles::TEXT_SIZE_NORMAL),
+                                .width(styles::PROFILE_PICK_LIST_WIDTH),
+                            ]
+                            .align_y(iced::Alignment::Center),
+                            row![
+                                text_input("Profile name", &self.profile_name_input)
+                                    .on_input(Message::SettingProfileNameChanged)
+                                    .on_submit(Message::SettingsConfirmAndModalHide),
+                                default_tooltip(
+                                    icon_button(concat!(
+                                        env!("CARGO_MANIFEST_DIR"),
+                                        "/resources/images/plus.svg"
+                                    ))
+                                    .style(button::secondary)
+                                    .on_press(Message::SettingProfileCreate),
+                                    "New profile"
+                                ),
+                                default_tooltip(
+                                    icon_button(concat!(
+                                        env!("CARGO_MANIFEST_DIR"),
+                                        "/resources/images/trash-2.svg"
+                                    ))
+                                    .style(button::danger)
+                                    .on_press_maybe(
+                                        (self.settings_modal_fields.profiles.len() > 1)
+                                            .then_some(Message::SettingProfileDelete)
+                                    ),
+                                    "Delete profile"
+                                ),
+                            ]
+                            .spacing(styles::COL_SPACING)
+                            .align_y(iced::Alignment::Center),
+                            self.profile_name_error.as_ref().map(|message| {
+                                text!("{}", message)
+                                    .size(styles::TEXT_SIZE_SMALL)
+                                    .style(text::danger)
+                            }),
+                            field_error(SettingsField::ProfileName),
+                            row![
+                                text("Sit time [min]:").size(styles::TEXT_SIZE_NORMAL),
+                                space::horizontal(),
+                                iced_aw::number_input(
+                                    &self.settings_modal_fields.profile().sit_duration_as_min,
+                                    0..=1440,
+                                    Message::SettingSitTimeChanged
+                                )
+                                .width(75)
+                                .step(1)
+                                .on_input(Message::SettingSitTimeChanged)
+                                .on_submit(Message::SettingsConfirmAndModalHide),
+                            ]
+                            .align_y(iced::Alignment::Center),
+                            field_error(SettingsField::SitDuration),
+                            row![
+                                text("Stand time [min]:").size(styles::TEXT_SIZE_NORMAL),
+                                space::horizontal(),
+                                iced_aw::number_input(
+                                    &self.settings_modal_fields.profile().stand_duration_as_min,
+                                    0..=1440,
+                                    Message::SettingStandTimeChanged
+                                )
+                                .width(75)
+                                .step(1)
+                                .on_input(Message::SettingStandTimeChanged)
+                                .on_submit(Message::SettingsConfirmAndModalHide),
+                            ]
+                            .align_y(iced::Alignment::Center),
+                            field_error(SettingsField::StandDuration),
+                            row![
+                                text("Standing goal [min/day]:").size(styles::TEXT_SIZE_NORMAL),
                                 space::horizontal(),
-                                radio(
-                                    "Standing",
-                                    Stance::Standing,
-                                    Some(self.settings_modal_fields.start_stance),
-                                    Message::SettingStartStanceChanged
+                                iced_aw::number_input(
+                                    &self.settings_modal_fields.daily_standing_goal_as_min,
+                                    0..=1440,
+                                    Message::SettingDailyStandingGoalChanged
                                 )
-                                .size(styles::TEXT_SIZE_NORMAL),
-                            ],
+                                .width(75)
+                                .step(5)
+                                .on_input(Message::SettingDailyStandingGoalChanged)
+                                .on_submit(Message::SettingsConfirmAndModalHide),
+                            ]
+                            .align_y(iced::Alignment::Center),
+                            field_error(SettingsField::DailyStandingGoal),
+                            row![
+                                text("Snooze time [min]:").size(styles::TEXT_SIZE_NORMAL),
+                                space::horizontal(),
+                                iced_aw::number_input(
+                                    &self.settings_modal_fields.snooze_duration_as_min,
+                                    1..=120,
+                                    Message::SettingSnoozeTimeChanged
+                                )
+                                .width(75)
+                                .step(1)
+                                .on_input(Message::SettingSnoozeTimeChanged)
+                                .on_submit(Message::SettingsConfirmAndModalHide),
+                            ]
+                            .align_y(iced::Alignment::Center),
+                            field_error(SettingsField::SnoozeDuration),
+                            column![
+                                text("Start stance:").size(styles::TEXT_SIZE_NORMAL),
+                                row![
+                                    radio(
+                                        "Sitting",
+                                        Stance::Sitting,
+                                        Some(self.settings_modal_fields.profile().start_stance),
+                                        Message::SettingStartStanceChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                    space::horizontal(),
+                                    radio(
+                                        "Standing",
+                                        Stance::Standing,
+                                        Some(self.settings_modal_fields.profile().start_stance),
+                                        Message::SettingStartStanceChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                ],
+                            ]
+                            .spacing(styles::COL_SPACING),
+                            column![
+                                text("Notifications:").size(styles::TEXT_SIZE_NORMAL),
+                                row![
+                                    radio(
+                                        "Desktop",
+                                        NotifierBackend::Desktop,
+                                        Some(self.settings_modal_fields.notifier_backend),
+                                        Message::SettingNotifierBackendChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                    space::horizontal(),
+                                    radio(
+                                        "In-app",
+                                        NotifierBackend::Banner,
+                                        Some(self.settings_modal_fields.notifier_backend),
+                                        Message::SettingNotifierBackendChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                    space::horizontal(),
+                                    radio(
+                                        "Log",
+                                        NotifierBackend::Log,
+                                        Some(self.settings_modal_fields.notifier_backend),
+                                        Message::SettingNotifierBackendChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                ],
+                            ]
+                            .spacing(styles::COL_SPACING),
+                            checkbox(self.settings_modal_fields.confirm_stance_change)
+                                .label("Confirm stance changes")
+                                .text_size(styles::TEXT_SIZE_NORMAL)
+                                .on_toggle(Message::SettingConfirmStanceChangeToggled),
+                            checkbox(self.settings_modal_fields.window.always_on_top)
+                                .label("Keep window on top")
+                                .text_size(styles::TEXT_SIZE_NORMAL)
+                                .on_toggle(Message::SettingAlwaysOnTopToggled),
+                            window_size_errors,
+                            checkbox(self.settings_modal_fields.escalation.enabled)
+                                .label("Escalate ignored reminders")
+                                .text_size(styles::TEXT_SIZE_NORMAL)
+                                .on_toggle(Message::SettingEscalationToggled),
+                            row![
+                                text("Repeat after [min]:").size(styles::TEXT_SIZE_NORMAL),
+                                space::horizontal(),
+                                iced_aw::number_input(
+                                    &self.settings_modal_fields.escalation.repeat_after_as_min,
+                                    1..=120,
+                                    Message::SettingEscalationRepeatTimeChanged
+                                )
+                                .width(75)
+                                .step(1)
+                                .on_input(Message::SettingEscalationRepeatTimeChanged)
+                                .on_submit(Message::SettingsConfirmAndModalHide),
+                            ]
+                            .align_y(iced::Alignment::Center),
+                            field_error(SettingsField::EscalationRepeatAfter),
+                            row![
+                                text("Repeat at most every [min]:").size(styles::TEXT_SIZE_NORMAL),
+                                space::horizontal(),
+                                iced_aw::number_input(
+                                    &self
+                                        .settings_modal_fields
+                                        .escalation
+                                        .max_repeat_interval_as_min,
+                                    1..=1440,
+                                    Message::SettingEscalationMaxRepeatIntervalChanged
+                                )
+                                .width(75)
+                                .step(1)
+                                .on_input(Message::SettingEscalationMaxRepeatIntervalChanged)
+                                .on_submit(Message::SettingsConfirmAndModalHide),
+                            ]
+                            .align_y(iced::Alignment::Center),
+                            field_error(SettingsField::EscalationMaxRepeatInterval),
+                            checkbox(self.settings_modal_fields.auto_pause.enabled)
+                                .label("Pause while away")
+                                .text_size(styles::TEXT_SIZE_NORMAL)
+                                .on_toggle(Message::SettingAutoPauseToggled),
+                            text(
+                                "Idle time comes from X11 or, on Wayland, from GNOME only. \
+                                Elsewhere only locking the screen counts as away."
+                            )
+                            .size(styles::TEXT_SIZE_SMALL),
+                            row![
+                                text("Away after idle [min]:").size(styles::TEXT_SIZE_NORMAL),
+                                space::horizontal(),
+                                iced_aw::number_input(
+                                    &self.settings_modal_fields.auto_pause.idle_threshold_as_min,
+                                    1..=1440,
+                                    Message::SettingAutoPauseIdleTimeChanged
+                                )
+                                .width(75)
+                                .step(1)
+                                .on_input(Message::SettingAutoPauseIdleTimeChanged)
+                                .on_submit(Message::SettingsConfirmAndModalHide),
+                            ]
+                            .align_y(iced::Alignment::Center),
+                            field_error(SettingsField::AutoPauseIdleThreshold),
+                            idle_time_warning,
+                            column![
+                                text("Running cycle on file reload:")
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                row![
+                                    radio(
+                                        "Keep",
+                                        RunningCyclePolicy::Keep,
+                                        Some(self.settings_modal_fields.running_cycle_on_reload),
+                                        Message::SettingRunningCycleOnReloadChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                    space::horizontal(),
+                                    radio(
+                                        "Recalculate",
+                                        RunningCyclePolicy::Recalculate,
+                                        Some(self.settings_modal_fields.running_cycle_on_reload),
+                                        Message::SettingRunningCycleOnReloadChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                ],
+                            ]
+                            .spacing(styles::COL_SPACING),
+                            column![
+                                text("Running cycle on restart:").size(styles::TEXT_SIZE_NORMAL),
+                                row![
+                                    radio(
+                                        "Continue",
+                                        RestartPolicy::Continue,
+                                        Some(self.settings_modal_fields.running_cycle_on_restart),
+                                        Message::SettingRunningCycleOnRestartChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                    space::horizontal(),
+                                    radio(
+                                        "Fresh",
+                                        RestartPolicy::Fresh,
+                                        Some(self.settings_modal_fields.running_cycle_on_restart),
+                                        Message::SettingRunningCycleOnRestartChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                    space::horizontal(),
+                                    radio(
+                                        "Ask",
+                                        RestartPolicy::Ask,
+                                        Some(self.settings_modal_fields.running_cycle_on_restart),
+                                        Message::SettingRunningCycleOnRestartChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                ],
+                            ]
+                            .spacing(styles::COL_SPACING),
+                            column![
+                                text("Running cycle after suspend:").size(styles::TEXT_SIZE_NORMAL),
+                                row![
+                                    radio(
+                                        "Continue",
+                                        WakePolicy::Continue,
+                                        Some(self.settings_modal_fields.wake_policy),
+                                        Message::SettingWakePolicyChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                    space::horizontal(),
+                                    radio(
+                                        "Break",
+                                        WakePolicy::Break,
+                                        Some(self.settings_modal_fields.wake_policy),
+                                        Message::SettingWakePolicyChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                    space::horizontal(),
+                                    radio(
+                                        "Restart",
+                                        WakePolicy::Restart,
+                                        Some(self.settings_modal_fields.wake_policy),
+                                        Message::SettingWakePolicyChanged
+                                    )
+                                    .size(styles::TEXT_SIZE_NORMAL),
+                                ],
+                            ]
+                            .spacing(styles::COL_SPACING),
                         ]
-                        .spacing(styles::COL_SPACING),
-                    ]
-                    .spacing(styles::COL_SPACING),
+                        .spacing(styles::COL_SPACING)
+                        .padding(iced::Padding::ZERO.right(styles::SCROLLBAR_GAP)),
+                    )
+                    .height(styles::SETTINGS_FIELDS_HEIGHT),
                     rule::horizontal(styles::HORIZONTAL_RULE_HEIGHT),
                     row![
                         default_tooltip(
@@ -429,10 +1492,12 @@ pub fn view(&self) -> iced::Element<'_, Message> {
                                 "/resources/images/circle-check.svg"
                             ),)
                             .style(button::success)
-                            .on_press(Message::SettingsConfirmAndModalHide),
+                            .on_press_maybe(
+                                (modal_errors.is_empty() && self.profile_name_error.is_none())
+                                    .then_some(Message::SettingsConfirmAndModalHide)
+                            ),
                             "Confirm"
                         ),
-                        space::horizontal(),
                         default_tooltip(
                             icon_button(concat!(
                                 env!("CARGO_MANIFEST_DIR"),
@@ -442,7 +1507,6 @@ pub fn view(&self) -> iced::Element<'_, Message> {
                             .on_press(Message::SettingsModalHide),
                             "Cancel"
                         ),
-                        space::horizontal(),
                         default_tooltip(
                             icon_button(concat!(
                                 env!("CARGO_MANIFEST_DIR"),
@@ -460,9 +1524,8 @@ pub fn view(&self) -> iced::Element<'_, Message> {
                             ),)
                             .style(button::primary)
                             .on_press(Message::SettingsSaveToFile),
-                            constcat::concat!("Save to file: ", SETTINGS_FILE_NAME),
+                            text!("Save to file: {}", settings_file_path().display()),
                         ),
-                        space::horizontal(),
                         default_tooltip(
                             icon_button(concat!(
                                 env!("CARGO_MANIFEST_DIR"),
@@ -470,11 +1533,19 @@ pub fn view(&self) -> iced::Element<'_, Message> {
                             ),)
                             .style(button::primary)
                             .on_press(Message::SettingsLoadFromFile),
-                            constcat::concat!("Load from file: ", SETTINGS_FILE_NAME),
+                            text!("Load from file: {}", settings_file_path().display()),
+                        ),
+                        default_tooltip(
+                            icon_button(concat!(
+                                env!("CARGO_MANIFEST_DIR"),
+                                "/resources/images/history.svg"
+                            ),)
+                            .style(button::secondary)
+                            .on_press(Message::SettingsRestorePrevious),
+                            "Restore previous settings file",
                         ),
                     ]
                     .width(iced::Length::Fill)
-                    .padding([0, styles::ROW_PADDING])
                     .spacing(styles::ROW_SPACING)
                     .align_y(iced::Alignment::Center)
                 ]
@@ -486,60 +1557,537 @@ pub fn view(&self) -> iced::Element<'_, Message> {
             .into();
 
             modal(main_content, modal_content, Message::SettingsModalHide)
+        } else if self.statistics_show {
+            let totals_row = |label: &'static str, today: String, week: String| {
+                row![
+                    text(label)
+                        .width(iced::Length::Fill)
+                        .size(styles::TEXT_SIZE_NORMAL),
+                    text(today)
+                        .width(styles::STATISTICS_COLUMN_WIDTH)
+                        .align_x(iced::Alignment::End)
+                        .size(styles::TEXT_SIZE_NORMAL),
+                    text(week)
+                        .width(styles::STATISTICS_COLUMN_WIDTH)
+                        .align_x(iced::Alignment::End)
+                        .size(styles::TEXT_SIZE_NORMAL),
+                ]
+                .padding([0, styles::ROW_PADDING])
+            };
+            let today = &self.statistics.today;
+            let week = &self.statistics.week;
+
+            let statistics_content: Element<'_, Message> = container(
+                column![
+                    text("Statistics").size(styles::TEXT_SIZE_HEADING),
+                    rule::horizontal(styles::HORIZONTAL_RULE_HEIGHT),
+                    column![
+                        totals_row("", "Today".to_string(), "Week".to_string()),
+                        totals_row(
+                            "Standing [min]:",
+                            today.standing_as_min().to_string(),
+                            week.standing_as_min().to_string(),
+                        ),
+                        totals_row(
+                            "Sitting [min]:",
+                            today.sitting_as_min().to_string(),
+                            week.sitting_as_min().to_string(),
+                        ),
+                        totals_row(
+                            "Completed cycles:",
+                            today.completed_cycles.to_string(),
+                            week.completed_cycles.to_string(),
+                        ),
+                        totals_row(
+                            "Skipped cycles:",
+                            today.skipped_cycles.to_string(),
+                            week.skipped_cycles.to_string(),
+                        ),
+                        totals_row(
+                            "Ignored reminders:",
+                            today.reminders.to_string(),
+                            week.reminders.to_string(),
+                        ),
+                    ]
+                    .spacing(styles::COL_SPACING),
+                    rule::horizontal(styles::HORIZONTAL_RULE_HEIGHT),
+                    canvas(WeekChart {
+                        days: self.statistics.week_days,
+                    })
+                    .width(iced::Length::Fill)
+                    .height(styles::STATISTICS_CHART_HEIGHT),
+                    rule::horizontal(styles::HORIZONTAL_RULE_HEIGHT),
+                    row![
+                        space::horizontal(),
+                        default_tooltip(
+                            icon_button(concat!(
+                                env!("CARGO_MANIFEST_DIR"),
+                                "/resources/images/circle-x.svg"
+                            ),)
+                            .style(button::secondary)
+                            .on_press(Message::StatisticsHide),
+                            "Close"
+                        ),
+                    ]
+                    .width(iced::Length::Fill)
+                    .padding([0, styles::ROW_PADDING])
+                ]
+                .spacing(styles::MAIN_COLUMN_SPACING),
+            )
+            .width(350)
+            .padding(styles::OUTER_PADDING)
+            .style(container::rounded_box)
+            .into();
+
+            modal(main_content, statistics_content, Message::StatisticsHide)
         } else {
             main_content
         }
     }
 
     pub fn theme(&self) -> Option<iced::Theme> {
-        self.theme.clone()
+        match self.settings.theme {
+            ThemeChoice::System => None,
+            ThemeChoice::Light => Some(iced::Theme::Light),
+            ThemeChoice::Dark => Some(iced::Theme::Dark),
+        }
+    }
+
+    pub fn title(&self) -> String {
+        const TITLE: &str = "RustNot";
+
+        // Flash the title while a stance change escalated up to raising the window.
+        match self.timer.current_cycle() {
+            Some(cycle_info)
+                if cycle_info.escalating
+                    && cycle_info.reminders_sent > 0
+                    && self
+                        .settings
+                        .escalation
+                        .step(cycle_info.reminders_sent)
+                        .raise_window
+                    && self
+                        .timer
+                        .since_last_notification()
+                        .is_some_and(|since| since.as_secs() % 2 == 0) =>
+            {
+                format!(
+                    "{} - {}",
+                    match cycle_info.stance {
+                        Stance::Sitting => "SIT DOWN",
+                        Stance::Standing => "STAND UP",
+                    },
+                    TITLE
+                )
+            }
+            _ => TITLE.to_string(),
+        }
     }
 }
 
 impl App {
-    fn start_new_cycle(&mut self) {
-        match self.current_timer_cycle {
-            None => {
-                self.current_timer_cycle = Some(TimerCycleInfo {
-                    start_time: Instant::now(),
-                    duration: Duration::from_mins(
-                        self.settings
-                            .get_duration_for_stance(&self.settings.start_stance),
+    fn handle_timer_event(&mut self, event: Option<TimerEvent>) -> iced::Task<Message> {
+        self.record_finished_cycles();
+        self.check_standing_goal();
+        if event.is_some() {
+            self.save_cycle_state();
+        }
+        let signal_task = match event {
+            Some(
+                TimerEvent::Started { .. }
+                | TimerEvent::StanceChanged { .. }
+                | TimerEvent::Snoozed { .. }
+                | TimerEvent::Restored { .. }
+                | TimerEvent::Woke {
+                    policy: WakePolicy::Restart,
+                    ..
+                },
+            ) => self.emit_stance_changed(),
+            _ => iced::Task::none(),
+        };
+
+        let task = match event {
+            Some(TimerEvent::StanceChanged { stance, duration }) => {
+                let body = if self.settings.confirm_stance_change {
+                    format!(
+                        "It's time to change your stance.\nConfirm with 'Done' to start the next {} min.",
+                        duration.as_secs() / 60
+                    )
+                } else {
+                    format!(
+                        "It's time to change your stance.\nNext reminder in: {} min.",
+                        duration.as_secs() / 60
+                    )
+                };
+                self.notify_stance_change(stance, body, ToastLevel::Info)
+            }
+            Some(TimerEvent::Reminder { stance, step }) => {
+                let body = if self.timer.is_awaiting_confirmation() {
+                    format!(
+                        "You did not confirm the stance change yet (reminder {}).\nConfirm with 'Done' to start the next cycle.",
+                        step.step
+                    )
+                } else {
+                    format!(
+                        "You did not react to the stance change yet (reminder {}).",
+                        step.step
+                    )
+                };
+                let level = if step.critical {
+                    ToastLevel::Critical
+                } else {
+                    ToastLevel::Info
+                };
+                let notify_task = self.notify_stance_change(stance, body, level);
+
+                if step.raise_window {
+                    iced::Task::batch([notify_task, raise_window()])
+                } else {
+                    notify_task
+                }
+            }
+            Some(TimerEvent::Woke { slept, policy }) => {
+                let body = match policy {
+                    WakePolicy::Continue => "The time asleep counts towards the current cycle.",
+                    WakePolicy::Break => "The current cycle goes on where it was.",
+                    WakePolicy::Restart => "The timer started over with the start stance.",
+                };
+                self.notifications.notify(Toast::info(
+                    "Welcome back",
+                    format!(
+                        "The system was suspended for {}.\n{}",
+                        format_countdown(slept),
+                        body
                     ),
-                    stace: self.settings.start_stance,
-                });
+                ));
+                iced::Task::none()
             }
-            Some(cycle_info) => {
-                let new_cycle_stance = Stance::inverted(cycle_info.stace);
-                let new_cycle_duration =
-                    Duration::from_mins(self.settings.get_duration_for_stance(&new_cycle_stance));
-                self.current_timer_cycle = Some(TimerCycleInfo {
-                    start_time: Instant::now(),
-                    duration: new_cycle_duration,
-                    stace: new_cycle_stance,
-                });
+            Some(TimerEvent::Acknowledged | TimerEvent::Snoozed { .. }) => {
+                // The stance change was answered, so its banner and buttons are obsolete.
+                if self
+                    .notifications
+                    .banner()
+                    .is_some_and(|toast| !toast.actions.is_empty())
+                {
+                    self.notifications.dismiss_banner();
+                }
+                iced::Task::none()
+            }
+            _ => iced::Task::none(),
+        };
+        // The buttons of a banner about an earlier cycle would do nothing.
+        if let Some(cycle_id) = self.notifications.banner().and_then(|toast| toast.cycle_id)
+            && !self.timer.is_current_cycle(cycle_id)
+        {
+            self.notifications.dismiss_banner();
+        }
+        iced::Task::batch([signal_task, task])
+    }
 
-                Notification::new()
-                    .summary(match new_cycle_stance {
-                        Stance::Sitting => "Please sit Down.",
-                        Stance::Standing => "Please stand up.",
-                    })
-                    .body(&format!(
-                        "It's time to change your stance.\nNext reminder in: {} min.",
-                        new_cycle_duration.as_secs() / 60
-                    ))
-                    .sound_name("dialog-information")
-                    .timeout(Timeout::Milliseconds(10 * 1000))
-                    .show()
-                    .expect("unable to toast");
+    /// Announce the stance of the cycle, which just began, on D-Bus.
+    fn emit_stance_changed(&self) -> iced::Task<Message> {
+        let status = self.status();
+        let (Some(connection), Some(stance)) = (self.dbus_connection.clone(), status.stance) else {
+            return iced::Task::none();
+        };
+        let remaining_as_sec = status.remaining_as_sec.unwrap_or_default();
+        iced::Task::future(async move {
+            let _ = dbus::emit_stance_changed(&connection, &stance, remaining_as_sec).await;
+        })
+        .discard()
+    }
+
+    fn restore_cycle(&mut self, saved: &SavedCycle, mode: RestoreMode) -> iced::Task<Message> {
+        let event = self.timer.restore_cycle(&self.settings, saved, mode);
+        self.handle_timer_event(event)
+    }
+
+    /// Keep the state file in sync with the current cycle, to continue it after a restart.
+    fn save_cycle_state(&mut self) {
+        if let Err(err) = CycleState::save_to_file(self.timer.save_cycle().as_ref()) {
+            self.notifications.notify(Toast::error(
+                "Failed saving cycle",
+                format!("An error occured while writing the state file: {}", err),
+            ));
+        }
+    }
+
+    fn record_finished_cycles(&mut self) {
+        let finished_cycles = self.timer.take_finished_cycles();
+        for cycle in &finished_cycles {
+            let record = CycleRecord::from(cycle);
+            self.standing_goal.add(&record);
+            if let Err(err) = record.append_to_file() {
+                self.notifications.notify(Toast::error(
+                    "Failed recording cycle",
+                    format!(
+                        "An error occured while writing the cycle to the history file: {}",
+                        err
+                    ),
+                ));
+            }
+        }
+        // The statistics also provide today's ignored reminders of the main window.
+        if !finished_cycles.is_empty() || self.statistics.date != Local::now().date_naive() {
+            self.reload_statistics();
+        }
+    }
+
+    /// Time spent in the running cycle, if it is a standing one.
+    fn running_standing_as_sec(&self) -> u64 {
+        match (self.timer.current_cycle(), self.timer.active_duration()) {
+            (Some(cycle_info), Some(active)) if cycle_info.stance == Stance::Standing => {
+                active.as_secs()
+            }
+            _ => 0,
+        }
+    }
+
+    fn check_standing_goal(&mut self) {
+        if self.standing_goal.check(
+            self.settings.daily_standing_goal_as_min,
+            self.running_standing_as_sec(),
+            Local::now().date_naive(),
+        ) {
+            self.notifications.notify(Toast::info(
+                "Daily standing goal reached",
+                format!(
+                    "You stood for {} min today. Well done!",
+                    self.settings.daily_standing_goal_as_min
+                ),
+            ));
+        }
+    }
+
+    fn reload_statistics(&mut self) {
+        let records = match CycleRecord::load_all_from_file() {
+            Err(err) => {
+                self.notifications.notify(Toast::error(
+                    "Failed loading history",
+                    format!("An error occured while reading the history file: {}", err),
+                ));
+                Vec::new()
+            }
+            Ok(records) => records,
+        };
+        self.statistics = Statistics::from_records(&records, Local::now().date_naive());
+    }
+
+    fn notify_stance_change(
+        &mut self,
+        stance: Stance,
+        body: String,
+        level: ToastLevel,
+    ) -> iced::Task<Message> {
+        let Some(cycle_id) = self.timer.current_cycle().map(|cycle_info| cycle_info.id) else {
+            return iced::Task::none();
+        };
+        let mut toast = Toast::info(
+            match stance {
+                Stance::Sitting => "Please sit Down.",
+                Stance::Standing => "Please stand up.",
+            },
+            body,
+        )
+        .with_action(ToastAction::Done, "Done")
+        .with_action(
+            ToastAction::Snooze,
+            format!("Snooze {} min", self.settings.snooze_duration_as_min),
+        )
+        .with_action(ToastAction::Skip, "Skip")
+        .for_cycle(cycle_id);
+        toast.level = level;
+
+        match self.notifications.notify(toast) {
+            Some(pending_action) => {
+                iced::Task::future(pending_action).then(move |action| match action {
+                    Ok(action) => iced::Task::done(Message::stance_change_answer(action, cycle_id)),
+                    Err(_) => iced::Task::none(),
+                })
+            }
+            None => iced::Task::none(),
+        }
+    }
+
+    /// Write the theme and window geometry to the settings file.
+    fn save_window_state(&mut self) {
+        self.window_state_changed_at = None;
+        match self.settings.save_window_state_to_file() {
+            Err(err) => {
+                self.notifications.notify(Toast::error(
+                    "Failed saving window state",
+                    format!(
+                        "An error occured while saving the theme and window size: {}",
+                        err
+                    ),
+                ));
+                self.settings_file_error = Some(err);
+            }
+            Ok(_) => self.settings_file_stamp = settings_file_stamp(),
+        }
+    }
+
+    /// Apply the settings file after it changed on disk.
+    fn reload_settings_file(&mut self) -> iced::Task<Message> {
+        let mut loaded_settings = match Settings::load_from_file() {
+            Err(err) => {
+                self.notifications.notify(Toast::error(
+                    "Failed reloading settings",
+                    format!(
+                        "The settings file changed, but could not be loaded: {}",
+                        err
+                    ),
+                ));
+                self.settings_file_error = Some(err);
+                return iced::Task::none();
             }
+            Ok(s) => s,
+        };
+        // Loading may have migrated the file.
+        self.settings_file_stamp = settings_file_stamp();
+        self.settings_file_error = None;
+
+        self.settings_overrides.apply(&mut loaded_settings);
+        let geometry_changed = loaded_settings.window != self.settings.window;
+        self.settings = loaded_settings;
+        self.notifications
+            .set_backend(self.settings.notifier_backend);
+        if self.settings.running_cycle_on_reload == RunningCyclePolicy::Recalculate {
+            self.timer.recalculate_duration(&self.settings);
+        }
+        if !self.settings_modal_show {
+            self.reset_modal_fields();
+        }
+        self.notifications.notify(Toast::info(
+            "Settings reloaded",
+            "The settings file changed and was applied.",
+        ));
+        if geometry_changed {
+            apply_window_geometry(self.settings.window)
+        } else {
+            iced::Task::none()
+        }
+    }
+
+    fn answer_ipc_request(&mut self, ipc_request: IpcRequest) -> iced::Task<Message> {
+        let (result, task) = self.handle_ipc_request(ipc_request.request);
+        ipc_request
+            .responder
+            .respond(Response::new(result, Some(self.status())));
+        task
+    }
+
+    /// Run a command received over the control socket or D-Bus, like the matching button
+    /// would.
+    fn handle_ipc_request(
+        &mut self,
+        request: Request,
+    ) -> (Result<(), String>, iced::Task<Message>) {
+        let message = match request {
+            Request::Start if self.timer.is_running() => {
+                return (
+                    Err("the timer is already running".to_string()),
+                    iced::Task::none(),
+                );
+            }
+            Request::Start => Message::TimerStart,
+            Request::Status => return (Ok(()), iced::Task::none()),
+            Request::Quit => {
+                let quit = iced::Task::future(async {
+                    async_io::Timer::after(Duration::from_millis(QUIT_DELAY_AS_MS)).await;
+                    Message::Quit
+                });
+                return (Ok(()), quit);
+            }
+            Request::SetProfile { profile } => {
+                if !self.settings.profiles.contains_key(&profile) {
+                    return (
+                        Err(format!("there is no profile named \"{}\"", profile)),
+                        iced::Task::none(),
+                    );
+                }
+                Message::ProfileSelected(profile)
+            }
+            _ if !self.timer.is_running() => {
+                return (
+                    Err("the timer is not running".to_string()),
+                    iced::Task::none(),
+                );
+            }
+            Request::Stop => Message::TimerStop,
+            Request::Skip => Message::ManualTimerCycleEnd,
+            Request::Pause if self.timer.is_paused() => {
+                return (
+                    Err("the timer is already paused".to_string()),
+                    iced::Task::none(),
+                );
+            }
+            Request::Pause if self.timer.is_awaiting_confirmation() => {
+                return (
+                    Err("a stance change awaiting confirmation cannot be paused".to_string()),
+                    iced::Task::none(),
+                );
+            }
+            Request::Pause => Message::TimerPause,
+            Request::Resume if !self.timer.is_paused() => {
+                return (
+                    Err("the timer is not paused".to_string()),
+                    iced::Task::none(),
+                );
+            }
+            Request::Resume => Message::TimerResume,
+        };
+
+        let starting = matches!(message, Message::TimerStart);
+        let task = self.update(message);
+        if starting && !self.timer.is_running() {
+            return (
+                Err("the timer cannot start with invalid settings".to_string()),
+                task,
+            );
+        }
+        (Ok(()), task)
+    }
+
+    /// State of the timer for the control socket.
+    fn status(&self) -> Status {
+        let cycle_info = self.timer.current_cycle();
+        Status {
+            running: self.timer.is_running(),
+            paused: self.timer.is_paused(),
+            awaiting_confirmation: self.timer.is_awaiting_confirmation(),
+            stance: cycle_info.map(|cycle_info| match cycle_info.stance {
+                Stance::Sitting => "Sitting".to_string(),
+                Stance::Standing => "Standing".to_string(),
+            }),
+            remaining_as_sec: self.timer.remaining().map(|remaining| remaining.as_secs()),
+            duration_as_sec: cycle_info.map(|cycle_info| cycle_info.duration.as_secs()),
+            profile: self.settings.active_profile.clone(),
         }
     }
 
     fn reset_modal_fields(&mut self) {
-        self.settings_modal_fields.sit_duration_as_min = self.settings.sit_duration_as_min;
-        self.settings_modal_fields.stand_duration_as_min = self.settings.stand_duration_as_min;
-        self.settings_modal_fields.start_stance = self.settings.start_stance;
+        self.settings_modal_fields.active_profile = self.settings.active_profile.clone();
+        self.settings_modal_fields.profiles = self.settings.profiles.clone();
+        self.settings_modal_fields.notifier_backend = self.settings.notifier_backend;
+        self.settings_modal_fields.snooze_duration_as_min = self.settings.snooze_duration_as_min;
+        self.settings_modal_fields.confirm_stance_change = self.settings.confirm_stance_change;
+        self.settings_modal_fields.escalation = self.settings.escalation;
+        self.settings_modal_fields.daily_standing_goal_as_min =
+            self.settings.daily_standing_goal_as_min;
+        self.settings_modal_fields.running_cycle_on_reload = self.settings.running_cycle_on_reload;
+        self.settings_modal_fields.running_cycle_on_restart =
+            self.settings.running_cycle_on_restart;
+        self.settings_modal_fields.wake_policy = self.settings.wake_policy;
+        self.settings_modal_fields.auto_pause = self.settings.auto_pause;
+        self.settings_modal_fields.theme = self.settings.theme;
+        self.settings_modal_fields.window = self.settings.window;
+        self.reset_profile_name_input();
+    }
+
+    fn reset_profile_name_input(&mut self) {
+        self.profile_name_input = self.settings_modal_fields.active_profile.clone();
+        self.profile_name_error = None;
     }
 
     fn hide_modal(&mut self) {
@@ -0,0 +1,223 @@
+//! Status bar output of `rustnotctl bar`.
+//!
+//! Every update shows the stance and the time until the next stance change, as the window
+//! shows it after "Next cycle in:". Formats:
+//!
+//! - `waybar`: a JSON object per line with `text`, `tooltip`, `class` and `percentage`, for a
+//!   custom module with `"return-type": "json"`
+//! - `i3bar`: the i3bar protocol, for `status_command` of i3 and sway, with click events read
+//!   from stdin
+//! - `plain`: the text per line, for polybar, i3blocks and similar bars
+//!
+//! Clicks are mapped to commands: the left button starts the timer, the right button skips
+//! the current cycle.
+
+use std::time::Duration;
+
+use rustnot::countdown::next_cycle_text;
+use rustnot::protocol::{Request, Status};
+use serde_json::json;
+
+/// Name of the block in the i3bar protocol.
+pub const I3BAR_BLOCK_NAME: &str = "rustnot";
+const I3BAR_HEADER: &str = "{\"version\":1,\"click_events\":true}\n[";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BarFormat {
+    Waybar,
+    I3bar,
+    Plain,
+}
+
+impl BarFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "waybar" => Some(BarFormat::Waybar),
+            "i3bar" => Some(BarFormat::I3bar),
+            "plain" => Some(BarFormat::Plain),
+            _ => None,
+        }
+    }
+
+    /// Printed once before the first update.
+    pub fn header(&self) -> Option<&'static str> {
+        match self {
+            BarFormat::I3bar => Some(I3BAR_HEADER),
+            BarFormat::Waybar | BarFormat::Plain => None,
+        }
+    }
+
+    /// One update, without line break. `status` is `None` if rustnot is not running.
+    pub fn render(&self, status: Option<&Status>) -> String {
+        match self {
+            BarFormat::Waybar => json!({
+                "text": text(status),
+                "tooltip": tooltip(status),
+                "class": class(status),
+                "percentage": percentage(status),
+            })
+            .to_string(),
+            BarFormat::I3bar => format!(
+                "[{}],",
+                json!({
+                    "name": I3BAR_BLOCK_NAME,
+                    "full_text": text(status),
+                })
+            ),
+            BarFormat::Plain => text(status),
+        }
+    }
+}
+
+fn text(status: Option<&Status>) -> String {
+    match status {
+        None => "-".to_string(),
+        Some(status) if !status.running => "Stopped".to_string(),
+        Some(status) => format!(
+            "{} {}",
+            status.stance.as_deref().unwrap_or_default(),
+            next_cycle_text(
+                status.remaining_as_sec.map(Duration::from_secs),
+                status.paused,
+                status.awaiting_confirmation,
+            )
+        ),
+    }
+}
+
+fn tooltip(status: Option<&Status>) -> String {
+    match status {
+        None => "rustnot is not running".to_string(),
+        Some(status) => format!(
+            "Next cycle in: {}\nProfile: {}",
+            next_cycle_text(
+                status.remaining_as_sec.map(Duration::from_secs),
+                status.paused,
+                status.awaiting_confirmation,
+            ),
+            status.profile
+        ),
+    }
+}
+
+/// CSS class of the waybar module.
+fn class(status: Option<&Status>) -> &'static str {
+    match status {
+        None => "unavailable",
+        Some(status) if !status.running => "stopped",
+        Some(status) if status.paused => "paused",
+        Some(status) if status.awaiting_confirmation => "waiting",
+        Some(status) if status.stance.as_deref() == Some("Standing") => "standing",
+        Some(_) => "sitting",
+    }
+}
+
+/// Share of the current cycle which already passed.
+fn percentage(status: Option<&Status>) -> u64 {
+    match status.and_then(|status| status.remaining_as_sec.zip(status.duration_as_sec)) {
+        Some((remaining_as_sec, duration_as_sec)) if duration_as_sec > 0 => {
+            100 * duration_as_sec.saturating_sub(remaining_as_sec) / duration_as_sec
+        }
+        _ => 0,
+    }
+}
+
+/// Command for a click with mouse `button`, numbered like in X11.
+pub fn click_request(button: u64) -> Option<Request> {
+    match button {
+        1 => Some(Request::Start),
+        3 => Some(Request::Skip),
+        _ => None,
+    }
+}
+
+/// Mouse button of an i3bar click event on the rustnot block.
+///
+/// Click events are sent as an endless JSON array, one event per line, e.g.
+/// `,{"name":"rustnot","button":1,"x":1800,"y":10}`.
+pub fn parse_click_event(line: &str) -> Option<u64> {
+    let event: serde_json::Value =
+        serde_json::from_str(line.trim().trim_start_matches(['[', ','])).ok()?;
+    if event["name"] != I3BAR_BLOCK_NAME {
+        return None;
+    }
+    event["button"].as_u64()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn running(stance: &str, remaining_as_sec: u64) -> Status {
+        Status {
+            running: true,
+            stance: Some(stance.to_string()),
+            remaining_as_sec: Some(remaining_as_sec),
+            duration_as_sec: Some(40 * 60),
+            profile: "Office".to_string(),
+            ..Status::default()
+        }
+    }
+
+    #[test]
+    fn waybar_shows_countdown_and_progress() {
+        let update: serde_json::Value =
+            serde_json::from_str(&BarFormat::Waybar.render(Some(&running("Sitting", 10 * 60))))
+                .unwrap();
+
+        assert_eq!(
+            update,
+            json!({
+                "text": "Sitting 00:10:00",
+                "tooltip": "Next cycle in: 00:10:00\nProfile: Office",
+                "class": "sitting",
+                "percentage": 75,
+            })
+        );
+    }
+
+    #[test]
+    fn states_without_countdown() {
+        let paused = Status {
+            paused: true,
+            ..running("Standing", 60)
+        };
+        let stopped = Status::default();
+
+        assert_eq!(BarFormat::Plain.render(Some(&paused)), "Standing Paused");
+        assert_eq!(class(Some(&paused)), "paused");
+        assert_eq!(BarFormat::Plain.render(Some(&stopped)), "Stopped");
+        assert_eq!(class(Some(&stopped)), "stopped");
+        assert_eq!(BarFormat::Plain.render(None), "-");
+        assert_eq!(class(None), "unavailable");
+        assert_eq!(percentage(None), 0);
+    }
+
+    #[test]
+    fn i3bar_updates_are_array_elements() {
+        assert_eq!(
+            BarFormat::I3bar.render(Some(&running("Standing", 90))),
+            "[{\"full_text\":\"Standing 00:01:30\",\"name\":\"rustnot\"}],"
+        );
+    }
+
+    #[test]
+    fn i3bar_clicks_start_and_skip() {
+        assert_eq!(parse_click_event("["), None);
+        assert_eq!(
+            parse_click_event("{\"name\":\"rustnot\",\"button\":1,\"x\":1800}"),
+            Some(1)
+        );
+        assert_eq!(
+            parse_click_event(",{\"name\":\"rustnot\",\"button\":3}"),
+            Some(3)
+        );
+        assert_eq!(
+            parse_click_event(",{\"name\":\"clock\",\"button\":1}"),
+            None
+        );
+        assert_eq!(click_request(1), Some(Request::Start));
+        assert_eq!(click_request(3), Some(Request::Skip));
+        assert_eq!(click_request(2), None);
+    }
+}
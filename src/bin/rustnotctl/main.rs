@@ -0,0 +1,263 @@
+//! Control a running rustnot over its control socket.
+
+mod bar;
+
+use bar::{BarFormat, click_request, parse_click_event};
+
+use std::io::{BufRead, BufReader, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::{env, io, thread};
+
+use rustnot::protocol::{Request, Response, Status, socket_path};
+
+const USAGE: &str = "\
+Usage: rustnotctl [OPTIONS] <COMMAND>
+
+Commands:
+  start                Start the timer
+  stop                 Stop the timer
+  pause                Pause the timer
+  resume               Resume the paused timer
+  skip                 End the current cycle and start the next one
+  status               Print the state of the timer
+  set-profile <NAME>   Make the settings profile NAME the active one
+  quit                 Exit rustnot
+  bar                  Print the countdown for a status bar, updated every second
+
+Options:
+  --socket <PATH>      Connect to this socket instead of the default one
+  -h, --help           Print this help and exit
+  -V, --version        Print the version and exit
+
+Options of bar:
+  --format <FORMAT>    Output format: waybar, i3bar or plain [default: plain]
+  --interval <SEC>     Seconds between updates [default: 1]
+  --once               Print a single update and exit; a click in $BLOCK_BUTTON is sent first
+
+The response of rustnot is printed as a line of JSON, except for bar.
+
+Exit codes:
+  0  Success
+  1  rustnot rejected the command
+  2  Invalid command line arguments
+  3  rustnot is not running or did not answer
+";
+
+const EXIT_REJECTED: u8 = 1;
+const EXIT_USAGE: u8 = 2;
+const EXIT_UNREACHABLE: u8 = 3;
+
+const RESPONSE_TIMEOUT_AS_SEC: u64 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Command {
+    Send(Request),
+    Bar,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BarOptions {
+    format: BarFormat,
+    interval: Duration,
+    once: bool,
+}
+
+pub fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let mut socket = None;
+    let mut command = None;
+    let mut bar_options = BarOptions {
+        format: BarFormat::Plain,
+        interval: Duration::from_secs(1),
+        once: false,
+    };
+    let mut bar_option_given = false;
+
+    while let Some(arg) = args.next() {
+        let parsed = match arg.as_str() {
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                return ExitCode::SUCCESS;
+            }
+            "-V" | "--version" => {
+                println!("rustnotctl {}", env!("CARGO_PKG_VERSION"));
+                return ExitCode::SUCCESS;
+            }
+            "--socket" => match args.next() {
+                Some(path) => {
+                    socket = Some(PathBuf::from(path));
+                    continue;
+                }
+                None => Err("Missing value for --socket".to_string()),
+            },
+            "--format" => match args.next().as_deref().map(BarFormat::parse) {
+                Some(Some(format)) => {
+                    bar_options.format = format;
+                    bar_option_given = true;
+                    continue;
+                }
+                Some(None) => {
+                    Err("Invalid value for --format: expected waybar, i3bar or plain".to_string())
+                }
+                None => Err("Missing value for --format".to_string()),
+            },
+            "--interval" => match args.next().map(|value| value.parse::<u64>()) {
+                Some(Ok(interval_as_sec)) if interval_as_sec > 0 => {
+                    bar_options.interval = Duration::from_secs(interval_as_sec);
+                    bar_option_given = true;
+                    continue;
+                }
+                Some(_) => {
+                    Err("Invalid value for --interval: expected seconds above 0".to_string())
+                }
+                None => Err("Missing value for --interval".to_string()),
+            },
+            "--once" => {
+                bar_options.once = true;
+                bar_option_given = true;
+                continue;
+            }
+            "start" => Ok(Command::Send(Request::Start)),
+            "stop" => Ok(Command::Send(Request::Stop)),
+            "pause" => Ok(Command::Send(Request::Pause)),
+            "resume" => Ok(Command::Send(Request::Resume)),
+            "skip" => Ok(Command::Send(Request::Skip)),
+            "status" => Ok(Command::Send(Request::Status)),
+            "set-profile" => match args.next() {
+                Some(profile) => Ok(Command::Send(Request::SetProfile { profile })),
+                None => Err("Missing profile name for set-profile".to_string()),
+            },
+            "quit" => Ok(Command::Send(Request::Quit)),
+            "bar" => Ok(Command::Bar),
+            _ => Err(format!("Unknown argument: {}", arg)),
+        };
+        let parsed = match (parsed, &command) {
+            (Ok(_), Some(_)) => Err("Only one command may be given".to_string()),
+            (parsed, _) => parsed,
+        };
+        match parsed {
+            Err(err) => return usage_error(&err),
+            Ok(parsed) => command = Some(parsed),
+        }
+    }
+
+    let path = socket.unwrap_or_else(socket_path);
+    let request = match command {
+        None => return usage_error("Missing command"),
+        Some(Command::Bar) => return run_bar(&path, bar_options),
+        Some(_) if bar_option_given => {
+            return usage_error("--format, --interval and --once are only valid for bar");
+        }
+        Some(Command::Send(request)) => request,
+    };
+
+    let response_line = match send(&path, &request) {
+        Err(err) => {
+            eprintln!("error: cannot reach rustnot at {}: {}", path.display(), err);
+            return ExitCode::from(EXIT_UNREACHABLE);
+        }
+        Ok(line) => line,
+    };
+    println!("{}", response_line.trim_end());
+
+    match serde_json::from_str::<Response>(&response_line) {
+        Ok(Response { ok: true, .. }) => ExitCode::SUCCESS,
+        Ok(Response {
+            error: Some(error), ..
+        }) => {
+            eprintln!("error: {}", error);
+            ExitCode::from(EXIT_REJECTED)
+        }
+        _ => ExitCode::from(EXIT_REJECTED),
+    }
+}
+
+fn usage_error(message: &str) -> ExitCode {
+    eprintln!("error: {}\n\nFor more information, try '--help'.", message);
+    ExitCode::from(EXIT_USAGE)
+}
+
+/// Print status bar updates until the bar goes away.
+fn run_bar(path: &Path, options: BarOptions) -> ExitCode {
+    let mut stdout = io::stdout();
+
+    if options.once {
+        // i3blocks passes the button of a click on the block, e.g. `BLOCK_BUTTON=1`.
+        if let Some(request) = env::var("BLOCK_BUTTON")
+            .ok()
+            .and_then(|button| button.parse().ok())
+            .and_then(click_request)
+        {
+            let _ = send(path, &request);
+        }
+        let _ = writeln!(stdout, "{}", options.format.render(status(path).as_ref()));
+        return ExitCode::SUCCESS;
+    }
+
+    // Clicks are answered right away instead of waiting for the next update.
+    let (clicked, click_received) = mpsc::channel();
+    if options.format == BarFormat::I3bar {
+        let path = path.to_path_buf();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines().map_while(Result::ok) {
+                if let Some(request) = parse_click_event(&line).and_then(click_request) {
+                    let _ = send(&path, &request);
+                    let _ = clicked.send(());
+                }
+            }
+        });
+    }
+
+    if let Some(header) = options.format.header()
+        && writeln!(stdout, "{}", header).is_err()
+    {
+        return ExitCode::SUCCESS;
+    }
+    loop {
+        let update = options.format.render(status(path).as_ref());
+        if writeln!(stdout, "{}", update).is_err() {
+            return ExitCode::SUCCESS;
+        }
+        let _ = click_received.recv_timeout(options.interval);
+    }
+}
+
+/// Status of the running rustnot, if it answers.
+fn status(path: &Path) -> Option<Status> {
+    let response_line = send(path, &Request::Status).ok()?;
+    serde_json::from_str::<Response>(&response_line)
+        .ok()?
+        .status
+}
+
+/// Send `request` and return the response line.
+#[cfg(unix)]
+fn send(path: &Path, request: &Request) -> std::io::Result<String> {
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(Duration::from_secs(RESPONSE_TIMEOUT_AS_SEC)))?;
+
+    let mut request_line = serde_json::to_string(request)?;
+    request_line.push('\n');
+    stream.write_all(request_line.as_bytes())?;
+
+    let mut response_line = String::new();
+    BufReader::new(stream).read_line(&mut response_line)?;
+    if response_line.is_empty() {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(response_line)
+}
+
+/// The control socket is a Unix domain socket, which this platform lacks.
+#[cfg(not(unix))]
+fn send(_path: &Path, _request: &Request) -> std::io::Result<String> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "rustnotctl is only supported on Unix",
+    ))
+}
@@ -0,0 +1,357 @@
+//! Command line arguments.
+//!
+//! Without a command the window is opened. Overrides only apply to the active profile of the
+//! running app and are not written to the settings file, unless the settings are saved from
+//! the settings window.
+
+use crate::settings::{MAX_DURATION_AS_MIN, Settings, Stance};
+use crate::settings_file::{SettingsFileError, settings_file_path};
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+use thiserror::Error;
+
+pub const USAGE: &str = "\
+Usage: rustnot [OPTIONS] [COMMAND]
+
+Options:
+  --config <PATH>          Use this settings file instead of searching for one
+  --sit <MIN>              Sit duration in minutes for this run
+  --stand <MIN>            Stand duration in minutes for this run
+  --start-stance <STANCE>  Start stance for this run: sitting or standing
+  --autostart-timer        Start the timer right away
+  --headless               Run without showing the window, e.g. for rustnotctl or status bars
+
+Commands:
+  --print-config           Print the effective settings as TOML and exit
+  --validate-config        Check the settings file and exit
+  -h, --help               Print this help and exit
+  -V, --version            Print the version and exit
+
+Exit codes:
+  0  Success
+  1  The settings file is invalid: it cannot be parsed or contains invalid values
+  2  Invalid command line arguments
+  3  The settings file does not exist or cannot be read
+";
+
+pub const EXIT_INVALID_CONFIG: u8 = 1;
+pub const EXIT_USAGE: u8 = 2;
+pub const EXIT_UNREADABLE_CONFIG: u8 = 3;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum CliError {
+    #[error("Unknown argument: {0}")]
+    UnknownArgument(String),
+    #[error("Missing value for {0}")]
+    MissingValue(&'static str),
+    #[error("Invalid value for {flag}: {value} ({reason})")]
+    InvalidValue {
+        flag: &'static str,
+        value: String,
+        reason: &'static str,
+    },
+    #[error("Only one command may be given, got {0} and {1}")]
+    MultipleCommands(&'static str, &'static str),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    /// Open the window.
+    #[default]
+    Run,
+    PrintConfig,
+    ValidateConfig,
+    Help,
+    Version,
+}
+
+impl Command {
+    fn flag(&self) -> &'static str {
+        match self {
+            Command::Run => "",
+            Command::PrintConfig => "--print-config",
+            Command::ValidateConfig => "--validate-config",
+            Command::Help => "--help",
+            Command::Version => "--version",
+        }
+    }
+}
+
+/// Values replacing the ones of the active profile.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct SettingsOverrides {
+    pub sit_duration_as_min: Option<u64>,
+    pub stand_duration_as_min: Option<u64>,
+    pub start_stance: Option<Stance>,
+}
+
+impl SettingsOverrides {
+    pub fn apply(&self, settings: &mut Settings) {
+        let profile = settings.profile_mut();
+        if let Some(sit_duration_as_min) = self.sit_duration_as_min {
+            profile.sit_duration_as_min = sit_duration_as_min;
+        }
+        if let Some(stand_duration_as_min) = self.stand_duration_as_min {
+            profile.stand_duration_as_min = stand_duration_as_min;
+        }
+        if let Some(start_stance) = self.start_stance {
+            profile.start_stance = start_stance;
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Cli {
+    pub command: Command,
+    pub config: Option<PathBuf>,
+    pub overrides: SettingsOverrides,
+    pub autostart_timer: bool,
+    pub headless: bool,
+}
+
+impl Cli {
+    /// Parse the arguments, without the program name.
+    ///
+    /// Values may be given as separate argument or joined with `=`, e.g. `--sit=30`.
+    pub fn parse(args: impl IntoIterator<Item = OsString>) -> Result<Self, CliError> {
+        let mut cli = Cli::default();
+        let mut args = args.into_iter();
+
+        while let Some(arg) = args.next() {
+            let arg = arg.to_string_lossy().into_owned();
+            let (flag, mut value) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => {
+                    (flag.to_string(), Some(value.to_string()))
+                }
+                _ => (arg.clone(), None),
+            };
+            let mut value_for = |flag: &'static str| match value.take() {
+                Some(value) => Ok(value),
+                None => match args.next() {
+                    Some(value) => Ok(value.to_string_lossy().into_owned()),
+                    None => Err(CliError::MissingValue(flag)),
+                },
+            };
+
+            let command = match flag.as_str() {
+                "--config" => {
+                    cli.config = Some(PathBuf::from(value_for("--config")?));
+                    None
+                }
+                "--sit" => {
+                    cli.overrides.sit_duration_as_min =
+                        Some(parse_duration("--sit", value_for("--sit")?)?);
+                    None
+                }
+                "--stand" => {
+                    cli.overrides.stand_duration_as_min =
+                        Some(parse_duration("--stand", value_for("--stand")?)?);
+                    None
+                }
+                "--start-stance" => {
+                    cli.overrides.start_stance = Some(parse_stance(value_for("--start-stance")?)?);
+                    None
+                }
+                "--autostart-timer" => {
+                    cli.autostart_timer = true;
+                    None
+                }
+                "--headless" => {
+                    cli.headless = true;
+                    None
+                }
+                "--print-config" => Some(Command::PrintConfig),
+                "--validate-config" => Some(Command::ValidateConfig),
+                "-h" | "--help" => Some(Command::Help),
+                "-V" | "--version" => Some(Command::Version),
+                _ => return Err(CliError::UnknownArgument(arg)),
+            };
+            if value.is_some() {
+                return Err(CliError::UnknownArgument(arg));
+            }
+
+            if let Some(command) = command {
+                if cli.command != Command::Run && cli.command != command {
+                    return Err(CliError::MultipleCommands(
+                        cli.command.flag(),
+                        command.flag(),
+                    ));
+                }
+                cli.command = command;
+            }
+        }
+
+        Ok(cli)
+    }
+}
+
+/// Print the settings file, with defaults for missing keys and the overrides applied.
+///
+/// Prints the default settings if the file does not exist.
+pub fn print_config(overrides: &SettingsOverrides) -> ExitCode {
+    let mut settings = match Settings::check_file(&settings_file_path()) {
+        Err(SettingsFileError::MissingFile { .. }) => Settings::default(),
+        Err(err) => return report_config_error(&err),
+        Ok(s) => s,
+    };
+    overrides.apply(&mut settings);
+
+    match toml::to_string_pretty(&settings) {
+        Err(err) => report_config_error(&SettingsFileError::ParseState { source: err }),
+        Ok(s) => {
+            print!("{}", s);
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+pub fn validate_config() -> ExitCode {
+    let path = settings_file_path();
+    match Settings::check_file(&path) {
+        Err(err) => report_config_error(&err),
+        Ok(_) => {
+            println!("{}: valid", path.display());
+            ExitCode::SUCCESS
+        }
+    }
+}
+
+fn report_config_error(err: &SettingsFileError) -> ExitCode {
+    eprintln!("error: {}", err);
+    for (label, detail) in err.details() {
+        eprintln!("  {}: {}", label, detail);
+    }
+    ExitCode::from(config_exit_code(err))
+}
+
+fn config_exit_code(err: &SettingsFileError) -> u8 {
+    match err {
+        SettingsFileError::MissingFile { .. } | SettingsFileError::Read { .. } => {
+            EXIT_UNREADABLE_CONFIG
+        }
+        SettingsFileError::ParseFile { .. }
+        | SettingsFileError::Invalid { .. }
+        | SettingsFileError::UnsupportedVersion { .. }
+        | SettingsFileError::Write { .. }
+        | SettingsFileError::ParseState { .. } => EXIT_INVALID_CONFIG,
+    }
+}
+
+fn parse_duration(flag: &'static str, value: String) -> Result<u64, CliError> {
+    match value.parse::<u64>() {
+        Ok(duration) if (1..=MAX_DURATION_AS_MIN).contains(&duration) => Ok(duration),
+        _ => Err(CliError::InvalidValue {
+            flag,
+            value,
+            reason: "expected minutes between 1 and 1440",
+        }),
+    }
+}
+
+fn parse_stance(value: String) -> Result<Stance, CliError> {
+    match value.to_lowercase().as_str() {
+        "sitting" | "sit" => Ok(Stance::Sitting),
+        "standing" | "stand" => Ok(Stance::Standing),
+        _ => Err(CliError::InvalidValue {
+            flag: "--start-stance",
+            value,
+            reason: "expected sitting or standing",
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Cli, CliError> {
+        Cli::parse(args.iter().map(OsString::from))
+    }
+
+    #[test]
+    fn no_arguments_open_the_window() {
+        assert_eq!(parse(&[]), Ok(Cli::default()));
+    }
+
+    #[test]
+    fn overrides_accept_separate_and_joined_values() {
+        let cli = parse(&[
+            "--config",
+            "/tmp/rustnot.toml",
+            "--sit=30",
+            "--stand",
+            "15",
+            "--start-stance=Standing",
+            "--autostart-timer",
+            "--headless",
+        ])
+        .unwrap();
+
+        assert_eq!(cli.command, Command::Run);
+        assert_eq!(cli.config, Some(PathBuf::from("/tmp/rustnot.toml")));
+        assert_eq!(
+            cli.overrides,
+            SettingsOverrides {
+                sit_duration_as_min: Some(30),
+                stand_duration_as_min: Some(15),
+                start_stance: Some(Stance::Standing),
+            }
+        );
+        assert!(cli.autostart_timer);
+        assert!(cli.headless);
+    }
+
+    #[test]
+    fn invalid_arguments_are_rejected() {
+        assert_eq!(parse(&["--sit"]), Err(CliError::MissingValue("--sit")));
+        assert!(matches!(
+            parse(&["--stand", "0"]),
+            Err(CliError::InvalidValue {
+                flag: "--stand",
+                ..
+            })
+        ));
+        assert!(matches!(
+            parse(&["--start-stance", "lying"]),
+            Err(CliError::InvalidValue { .. })
+        ));
+        assert_eq!(
+            parse(&["--autostart-timer=yes"]),
+            Err(CliError::UnknownArgument(
+                "--autostart-timer=yes".to_string()
+            ))
+        );
+        assert_eq!(
+            parse(&["--frobnicate"]),
+            Err(CliError::UnknownArgument("--frobnicate".to_string()))
+        );
+        assert_eq!(
+            parse(&["--print-config", "--validate-config"]),
+            Err(CliError::MultipleCommands(
+                "--print-config",
+                "--validate-config"
+            ))
+        );
+    }
+
+    #[test]
+    fn overrides_replace_active_profile_values() {
+        let mut settings = Settings::default();
+        let untouched = settings.profile();
+        SettingsOverrides {
+            stand_duration_as_min: Some(25),
+            ..SettingsOverrides::default()
+        }
+        .apply(&mut settings);
+
+        assert_eq!(settings.profile().stand_duration_as_min, 25);
+        assert_eq!(
+            settings.profile().sit_duration_as_min,
+            untouched.sit_duration_as_min
+        );
+        assert_eq!(settings.profile().start_stance, untouched.start_stance);
+    }
+}
@@ -1,8 +1,9 @@
-use crate::styles::{self, tooltip_style};
+use crate::stats::StanceTotals;
+use crate::styles::{self, banner_style, tooltip_style};
 use iced::time::milliseconds;
 use iced::widget::{
-    Button, Svg, Tooltip, button, center, container, mouse_area, opaque, row, stack, svg, text,
-    tooltip,
+    Button, Svg, Tooltip, button, canvas, center, column, container, mouse_area, opaque, row,
+    stack, svg, text, tooltip,
 };
 
 pub fn modal<'a, Message>(
@@ -83,3 +84,199 @@ pub fn button_with_icon<'a, Message: 'a>(
     )
     .padding(styles::BUTTON_PADDING)
 }
+
+pub fn banner<'a, Message>(
+    base: impl Into<iced::Element<'a, Message>>,
+    summary: impl text::IntoFragment<'a>,
+    body: impl text::IntoFragment<'a>,
+    is_error: bool,
+    actions: Vec<(&'a str, Message)>,
+    on_dismiss: Message,
+) -> iced::Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let action_buttons = row(actions.into_iter().map(|(label, message)| {
+        button(text(label).size(styles::TEXT_SIZE_SMALL))
+            .padding([2, 6])
+            .style(button::secondary)
+            .on_press(message)
+            .into()
+    }))
+    .spacing(styles::COL_SPACING);
+
+    let banner_content = container(
+        row![
+            column![
+                text(summary).size(styles::TEXT_SIZE_SMALL),
+                text(body).size(styles::TEXT_SIZE_SMALL),
+                action_buttons,
+            ]
+            .spacing(styles::COL_SPACING)
+            .width(iced::Length::Fill),
+            button(icon(
+                concat!(env!("CARGO_MANIFEST_DIR"), "/resources/images/circle-x.svg"),
+                Some(16)
+            ))
+            .padding(3)
+            .style(button::text)
+            .on_press(on_dismiss),
+        ]
+        .spacing(styles::ROW_SPACING)
+        .align_y(iced::Alignment::Center),
+    )
+    .width(iced::Length::Fill)
+    .padding(styles::BANNER_PADDING)
+    .style(move |theme| banner_style(theme, is_error));
+
+    stack![
+        base.into(),
+        container(opaque(banner_content))
+            .height(iced::Length::Fill)
+            .align_y(iced::Alignment::End)
+            .padding(styles::BANNER_PADDING)
+    ]
+    .into()
+}
+
+/// Panel listing labeled error details, shown on top of the base until dismissed.
+pub fn error_panel<'a, Message>(
+    base: impl Into<iced::Element<'a, Message>>,
+    title: &'a str,
+    details: Vec<(&'a str, String)>,
+    on_dismiss: Message,
+) -> iced::Element<'a, Message>
+where
+    Message: Clone + 'a,
+{
+    let detail_rows = column(details.into_iter().map(|(label, value)| {
+        row![
+            text!("{}:", label)
+                .size(styles::TEXT_SIZE_SMALL)
+                .width(styles::ERROR_PANEL_LABEL_WIDTH),
+            text(value)
+                .size(styles::TEXT_SIZE_SMALL)
+                .width(iced::Length::Fill),
+        ]
+        .into()
+    }))
+    .spacing(styles::COL_SPACING);
+
+    let panel_content = container(
+        column![
+            row![
+                text(title)
+                    .size(styles::TEXT_SIZE_NORMAL)
+                    .width(iced::Length::Fill),
+                button(icon(
+                    concat!(env!("CARGO_MANIFEST_DIR"), "/resources/images/circle-x.svg"),
+                    Some(16)
+                ))
+                .padding(3)
+                .style(button::text)
+                .on_press(on_dismiss),
+            ]
+            .align_y(iced::Alignment::Center),
+            detail_rows,
+        ]
+        .spacing(styles::COL_SPACING),
+    )
+    .width(iced::Length::Fill)
+    .padding(styles::BANNER_PADDING)
+    .style(|theme| banner_style(theme, true));
+
+    stack![
+        base.into(),
+        container(opaque(panel_content))
+            .height(iced::Length::Fill)
+            .align_y(iced::Alignment::Start)
+            .padding(styles::BANNER_PADDING)
+    ]
+    .into()
+}
+
+/// Bar chart of the standing and sitting minutes for each day of a week, starting on Monday.
+#[derive(Debug)]
+pub struct WeekChart {
+    pub days: [StanceTotals; 7],
+}
+
+impl<Message> canvas::Program<Message> for WeekChart {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &iced::Renderer,
+        theme: &iced::Theme,
+        bounds: iced::Rectangle,
+        _cursor: iced::mouse::Cursor,
+    ) -> Vec<canvas::Geometry> {
+        const WEEKDAYS: [&str; 7] = ["Mo", "Tu", "We", "Th", "Fr", "Sa", "Su"];
+        const LABEL_HEIGHT: f32 = 16.0;
+        const LEGEND_HEIGHT: f32 = 16.0;
+
+        let palette = theme.extended_palette();
+        let standing_color = palette.success.base.color;
+        let sitting_color = palette.primary.base.color;
+        let text_color = palette.background.base.text;
+
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let chart_height = bounds.height - LABEL_HEIGHT - LEGEND_HEIGHT;
+        // Scale to at least one hour, so a few minutes do not fill the whole chart.
+        let max_as_sec = self
+            .days
+            .iter()
+            .map(|day| day.standing_as_sec.max(day.sitting_as_sec))
+            .max()
+            .unwrap_or(0)
+            .max(60 * 60) as f32;
+        let slot_width = bounds.width / WEEKDAYS.len() as f32;
+        let bar_width = slot_width * 0.3;
+
+        for (idx, (day, label)) in self.days.iter().zip(WEEKDAYS).enumerate() {
+            let slot_x = slot_width * idx as f32;
+            let bars = [
+                (day.standing_as_sec, standing_color),
+                (day.sitting_as_sec, sitting_color),
+            ];
+            for (bar_idx, (value_as_sec, color)) in bars.into_iter().enumerate() {
+                let height = chart_height * value_as_sec as f32 / max_as_sec;
+                frame.fill_rectangle(
+                    iced::Point::new(
+                        slot_x + slot_width * 0.2 + bar_width * bar_idx as f32,
+                        LEGEND_HEIGHT + chart_height - height,
+                    ),
+                    iced::Size::new(bar_width, height),
+                    color,
+                );
+            }
+            frame.fill_text(canvas::Text {
+                content: label.to_string(),
+                position: iced::Point::new(slot_x + slot_width / 2.0, bounds.height),
+                color: text_color,
+                size: 12.into(),
+                align_x: text::Alignment::Center,
+                align_y: iced::alignment::Vertical::Bottom,
+                ..canvas::Text::default()
+            });
+        }
+
+        for (idx, (label, color)) in [("Standing", standing_color), ("Sitting", sitting_color)]
+            .into_iter()
+            .enumerate()
+        {
+            let x = 80.0 * idx as f32;
+            frame.fill_rectangle(iced::Point::new(x, 3.0), iced::Size::new(10.0, 10.0), color);
+            frame.fill_text(canvas::Text {
+                content: label.to_string(),
+                position: iced::Point::new(x + 14.0, 0.0),
+                color: text_color,
+                size: 12.into(),
+                ..canvas::Text::default()
+            });
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
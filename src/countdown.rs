@@ -0,0 +1,58 @@
+//! Countdown texts shown in the window and in status bars, shared with `rustnotctl`.
+
+use std::time::Duration;
+
+/// Format a duration as `hh:mm:ss` for the countdown display.
+pub fn format_countdown(duration: Duration) -> String {
+    const MINUTE: u64 = 60;
+    const HOUR: u64 = 60 * MINUTE;
+
+    let duration_as_sec = duration.as_secs();
+    format!(
+        "{:0>2}:{:0>2}:{:0>2}",
+        duration_as_sec / HOUR,
+        (duration_as_sec % HOUR) / MINUTE,
+        duration_as_sec % MINUTE,
+    )
+}
+
+/// Time until the next stance change, as shown after "Next cycle in:".
+///
+/// `remaining` is `None` while the timer is stopped.
+pub fn next_cycle_text(remaining: Option<Duration>, paused: bool, awaiting: bool) -> String {
+    match remaining {
+        Some(_) if paused => "Paused".to_string(),
+        Some(_) if awaiting => "Waiting for you".to_string(),
+        Some(remaining) => format_countdown(remaining),
+        None => "-".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECOND: Duration = Duration::from_secs(1);
+    const MINUTE: Duration = Duration::from_mins(1);
+    const HOUR: Duration = Duration::from_hours(1);
+
+    #[test]
+    fn countdown_is_formatted_as_hours_minutes_seconds() {
+        assert_eq!(format_countdown(Duration::ZERO), "00:00:00");
+        assert_eq!(format_countdown(59 * SECOND), "00:00:59");
+        assert_eq!(format_countdown(45 * MINUTE + 3 * SECOND), "00:45:03");
+        assert_eq!(format_countdown(24 * HOUR + MINUTE), "24:01:00");
+        assert_eq!(format_countdown(Duration::from_millis(1999)), "00:00:01");
+    }
+
+    #[test]
+    fn paused_and_unconfirmed_cycles_have_no_countdown() {
+        assert_eq!(next_cycle_text(Some(MINUTE), false, false), "00:01:00");
+        assert_eq!(next_cycle_text(Some(MINUTE), true, false), "Paused");
+        assert_eq!(
+            next_cycle_text(Some(MINUTE), false, true),
+            "Waiting for you"
+        );
+        assert_eq!(next_cycle_text(None, false, false), "-");
+    }
+}
@@ -0,0 +1,323 @@
+//! The timer as D-Bus service `org.rustnot.Timer` on the session bus.
+//!
+//! Object `/org/rustnot/Timer`, interface `org.rustnot.Timer`:
+//!
+//! - methods `Start`, `Stop`, `Pause`, `Resume` and `Skip`, failing with
+//!   `org.freedesktop.DBus.Error.Failed` when the app rejects them, e.g. `Pause` while the
+//!   timer is stopped
+//! - method `Quit`, exiting the app after the call returned
+//! - properties `Stance` (`s`, `Sitting` or `Standing`, empty while stopped) and `Remaining`
+//!   (`t`, seconds until the next stance change). They change all the time, so no
+//!   `PropertiesChanged` signal is sent; listen to `StanceChanged` instead.
+//! - signal `StanceChanged(s stance, t remaining)`, sent whenever a cycle begins: on start,
+//!   stance changes, snoozes, restores and restarts after a suspend
+//!
+//! Method calls and property reads are passed on to the app like the requests of the control
+//! socket, see [`ipc`](crate::ipc).
+
+use crate::remote::{IpcRequest, Responder};
+
+use iced::futures::channel::{mpsc, oneshot};
+use iced::futures::{SinkExt, Stream, StreamExt};
+use rustnot::protocol::{Request, Response, Status};
+use zbus::fdo::{self, RequestNameFlags, RequestNameReply};
+use zbus::object_server::SignalEmitter;
+
+pub const BUS_NAME: &str = "org.rustnot.Timer";
+pub const OBJECT_PATH: &str = "/org/rustnot/Timer";
+
+#[derive(Debug, Clone)]
+pub enum DBusEvent {
+    /// The service is available; the connection is used to send signals.
+    Connected(zbus::Connection),
+    Request(IpcRequest),
+    Failed(String),
+}
+
+struct TimerService {
+    requests: mpsc::Sender<IpcRequest>,
+}
+
+impl TimerService {
+    /// Pass `request` on to the app and wait for its answer.
+    async fn call(&self, request: Request) -> fdo::Result<Status> {
+        let (sender, receiver) = oneshot::channel();
+        let sent = self
+            .requests
+            .clone()
+            .send(IpcRequest {
+                request,
+                responder: Responder::new(sender),
+            })
+            .await;
+        let response = match (sent, receiver.await) {
+            (Ok(_), Ok(response)) => response,
+            _ => return Err(fdo::Error::Failed("rustnot is shutting down".to_string())),
+        };
+        match response {
+            Response {
+                ok: true, status, ..
+            } => Ok(status.unwrap_or_default()),
+            Response { error, .. } => Err(fdo::Error::Failed(error.unwrap_or_default())),
+        }
+    }
+}
+
+#[zbus::interface(name = "org.rustnot.Timer")]
+impl TimerService {
+    async fn start(&self) -> fdo::Result<()> {
+        self.call(Request::Start).await.map(|_| ())
+    }
+
+    async fn stop(&self) -> fdo::Result<()> {
+        self.call(Request::Stop).await.map(|_| ())
+    }
+
+    async fn pause(&self) -> fdo::Result<()> {
+        self.call(Request::Pause).await.map(|_| ())
+    }
+
+    async fn resume(&self) -> fdo::Result<()> {
+        self.call(Request::Resume).await.map(|_| ())
+    }
+
+    async fn skip(&self) -> fdo::Result<()> {
+        self.call(Request::Skip).await.map(|_| ())
+    }
+
+    async fn quit(&self) -> fdo::Result<()> {
+        self.call(Request::Quit).await.map(|_| ())
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    async fn stance(&self) -> fdo::Result<String> {
+        Ok(self.call(Request::Status).await?.stance.unwrap_or_default())
+    }
+
+    #[zbus(property(emits_changed_signal = "false"))]
+    async fn remaining(&self) -> fdo::Result<u64> {
+        Ok(self
+            .call(Request::Status)
+            .await?
+            .remaining_as_sec
+            .unwrap_or_default())
+    }
+
+    #[zbus(signal, name = "StanceChanged")]
+    async fn emit_stance_changed(
+        emitter: &SignalEmitter<'_>,
+        stance: &str,
+        remaining: u64,
+    ) -> zbus::Result<()>;
+}
+
+/// Send the `StanceChanged` signal.
+pub async fn emit_stance_changed(
+    connection: &zbus::Connection,
+    stance: &str,
+    remaining_as_sec: u64,
+) -> zbus::Result<()> {
+    let emitter = SignalEmitter::new(connection, OBJECT_PATH)?;
+    TimerService::emit_stance_changed(&emitter, stance, remaining_as_sec).await
+}
+
+/// Events of the service on the session bus, for a subscription.
+///
+/// Emits `Failed` and ends if the name cannot be acquired, e.g. because another instance
+/// owns it.
+pub fn events() -> impl Stream<Item = DBusEvent> {
+    events_on(None)
+}
+
+/// Events of the service on the bus at `address`, or the session bus.
+fn events_on(address: Option<String>) -> impl Stream<Item = DBusEvent> {
+    iced::stream::channel(1, async move |mut output| {
+        let (sender, mut requests) = mpsc::channel(1);
+        let connection = match serve(address, TimerService { requests: sender }).await {
+            Err(err) => {
+                let _ = output.send(DBusEvent::Failed(err.to_string())).await;
+                return;
+            }
+            Ok(connection) => connection,
+        };
+        if output
+            .send(DBusEvent::Connected(connection.clone()))
+            .await
+            .is_err()
+        {
+            return;
+        }
+
+        while let Some(request) = requests.next().await {
+            if output.send(DBusEvent::Request(request)).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+async fn serve(address: Option<String>, service: TimerService) -> zbus::Result<zbus::Connection> {
+    let builder = match address {
+        Some(address) => zbus::connection::Builder::address(address.as_str())?,
+        None => zbus::connection::Builder::session()?,
+    };
+    let connection = builder.serve_at(OBJECT_PATH, service)?.build().await?;
+    // Requesting the name with the builder would not fail if it is taken.
+    match connection
+        .request_name_with_flags(BUS_NAME, RequestNameFlags::DoNotQueue.into())
+        .await?
+    {
+        RequestNameReply::PrimaryOwner | RequestNameReply::AlreadyOwner => Ok(connection),
+        RequestNameReply::Exists | RequestNameReply::InQueue => Err(zbus::Error::NameTaken),
+    }
+}
+
+/// Private bus for tests.
+///
+/// Tests using it need `dbus-daemon` and are ignored by default; run them with
+/// `cargo test -- --ignored`.
+#[cfg(test)]
+pub mod test_bus {
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+
+    use zbus::blocking::Connection;
+
+    /// Private session bus, stopped when dropped.
+    pub struct TestBus {
+        daemon: Child,
+        pub address: String,
+    }
+
+    impl TestBus {
+        /// Start a `dbus-daemon`.
+        ///
+        /// Panics if it is not installed, so a test cannot pass without running.
+        pub fn start() -> Self {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .expect("dbus-daemon is required for this test");
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take().unwrap())
+                .read_line(&mut address)
+                .unwrap();
+            Self {
+                daemon,
+                address: address.trim().to_string(),
+            }
+        }
+
+        pub fn connect(&self, name: Option<&str>) -> Connection {
+            let mut builder =
+                zbus::blocking::connection::Builder::address(self.address.as_str()).unwrap();
+            if let Some(name) = name {
+                builder = builder.name(name.to_string()).unwrap();
+            }
+            builder.build().unwrap()
+        }
+    }
+
+    impl Drop for TestBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use test_bus::TestBus;
+
+    use std::{sync, thread};
+
+    use zbus::MatchRule;
+    use zbus::blocking::{MessageIterator, Proxy};
+    use zbus::message::Type as MessageType;
+
+    /// Run the service on a background thread, answering requests like a stopped timer that
+    /// only accepts `Start`.
+    fn spawn_service(address: String) -> sync::mpsc::Receiver<zbus::Connection> {
+        let (sender, receiver) = sync::mpsc::channel();
+        thread::spawn(move || {
+            async_io::block_on(events_on(Some(address)).for_each(|event| {
+                match event {
+                    DBusEvent::Connected(connection) => {
+                        let _ = sender.send(connection);
+                    }
+                    DBusEvent::Request(ipc_request) => {
+                        let status = Status {
+                            stance: Some("Sitting".to_string()),
+                            remaining_as_sec: Some(2400),
+                            ..Status::default()
+                        };
+                        let result = match ipc_request.request {
+                            Request::Start | Request::Status => Ok(()),
+                            _ => Err("the timer is not running".to_string()),
+                        };
+                        ipc_request
+                            .responder
+                            .respond(Response::new(result, Some(status)));
+                    }
+                    DBusEvent::Failed(err) => panic!("service failed: {}", err),
+                }
+                async {}
+            }))
+        });
+        receiver
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn service_forwards_calls_and_sends_signals() {
+        let bus = TestBus::start();
+        let service_connection = spawn_service(bus.address.clone()).recv().unwrap();
+
+        let client = bus.connect(None);
+        let proxy = Proxy::new(&client, BUS_NAME, OBJECT_PATH, "org.rustnot.Timer").unwrap();
+        let mut signals = MessageIterator::for_match_rule(
+            MatchRule::builder()
+                .msg_type(MessageType::Signal)
+                .interface("org.rustnot.Timer")
+                .unwrap()
+                .member("StanceChanged")
+                .unwrap()
+                .build(),
+            &client,
+            None,
+        )
+        .unwrap();
+
+        proxy.call::<_, _, ()>("Start", &()).unwrap();
+        let err = proxy.call::<_, _, ()>("Pause", &()).unwrap_err();
+        assert!(err.to_string().contains("the timer is not running"));
+        assert_eq!(
+            proxy.get_property::<String>("Stance").unwrap(),
+            "Sitting".to_string()
+        );
+        assert_eq!(proxy.get_property::<u64>("Remaining").unwrap(), 2400);
+
+        async_io::block_on(emit_stance_changed(&service_connection, "Standing", 1200)).unwrap();
+        let signal = signals.next().unwrap().unwrap();
+        let (stance, remaining): (String, u64) = signal.body().deserialize().unwrap();
+        assert_eq!((stance.as_str(), remaining), ("Standing", 1200));
+    }
+
+    #[test]
+    #[ignore = "needs dbus-daemon"]
+    fn second_instance_does_not_take_over_name() {
+        let bus = TestBus::start();
+        let _owner = bus.connect(Some(BUS_NAME));
+
+        let first_event = async_io::block_on(async {
+            let mut events = std::pin::pin!(events_on(Some(bus.address.clone())));
+            events.next().await
+        });
+        assert!(matches!(first_event, Some(DBusEvent::Failed(_))));
+    }
+}
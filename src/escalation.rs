@@ -0,0 +1,39 @@
+use crate::settings::EscalationPolicy;
+
+use iced::time::Duration;
+
+/// What to do for a single repeated reminder of an ignored stance change.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscalationStep {
+    /// Number of the reminder, starting at 1 for the first repetition.
+    pub step: u32,
+    pub critical: bool,
+    pub raise_window: bool,
+}
+
+impl EscalationPolicy {
+    /// Delay after the previous notification until reminder `step` is due.
+    ///
+    /// Doubles with each step, up to `max_repeat_interval_as_min`.
+    pub fn delay_before_step(&self, step: u32) -> Duration {
+        let repeat_after = Duration::from_mins(self.repeat_after_as_min.max(1));
+        repeat_after
+            .saturating_mul(2u32.saturating_pow(step.saturating_sub(1)))
+            .min(Duration::from_mins(self.max_repeat_interval_as_min).max(repeat_after))
+    }
+
+    /// Escalation for reminder `step`. A threshold of `0` disables the respective escalation.
+    ///
+    /// Without escalation enabled, e.g. for the reminders of confirm mode, every step is plain.
+    pub fn step(&self, step: u32) -> EscalationStep {
+        EscalationStep {
+            step,
+            critical: self.enabled
+                && self.critical_from_step != 0
+                && step >= self.critical_from_step,
+            raise_window: self.enabled
+                && self.raise_window_from_step != 0
+                && step >= self.raise_window_from_step,
+        }
+    }
+}
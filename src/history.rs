@@ -0,0 +1,143 @@
+//! History of all finished cycles.
+//!
+//! Every finished cycle is appended to the history file in the data directory, see
+//! [`paths`](crate::paths), as one line of JSON. The format of the lines is documented in the
+//! _History_ section of the README.
+
+use crate::paths;
+use crate::settings::Stance;
+use crate::timer::{CycleEnd, FinishedCycle};
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local};
+use thiserror::Error;
+
+pub const HISTORY_FILE_NAME: &str = "rustnot_history.jsonl";
+
+pub fn history_file_path() -> PathBuf {
+    paths::history_file_path(HISTORY_FILE_NAME)
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum HistoryFileError {
+    #[error("Failed to read the history file.")]
+    Read,
+    #[error("Failed to write the history file.")]
+    Write,
+    #[error("Failed to serialize the cycle record.")]
+    Serialize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CycleRecord {
+    pub timestamp: DateTime<Local>,
+    pub stance: Stance,
+    pub planned_duration_as_sec: u64,
+    pub actual_duration_as_sec: u64,
+    pub end: CycleEnd,
+    pub acknowledged: bool,
+    pub reminders: u32,
+    #[serde(default)]
+    pub profile: Option<String>,
+}
+
+impl From<&FinishedCycle> for CycleRecord {
+    fn from(cycle: &FinishedCycle) -> Self {
+        Self {
+            timestamp: DateTime::<Local>::from(cycle.started_at),
+            stance: cycle.stance,
+            planned_duration_as_sec: cycle.planned_duration.as_secs(),
+            actual_duration_as_sec: cycle.actual_duration.as_secs(),
+            end: cycle.end,
+            acknowledged: cycle.acknowledged,
+            reminders: cycle.reminders,
+            profile: Some(cycle.profile.clone()),
+        }
+    }
+}
+
+impl CycleRecord {
+    pub fn to_line(&self) -> Result<String, HistoryFileError> {
+        serde_json::to_string(self).map_err(|_| HistoryFileError::Serialize)
+    }
+
+    pub fn from_line(line: &str) -> Option<Self> {
+        serde_json::from_str(line).ok()
+    }
+
+    pub fn append_to_file(&self) -> Result<(), HistoryFileError> {
+        let line = self.to_line()?;
+        let path = history_file_path();
+        if paths::create_parent_dir(&path).is_err() {
+            return Err(HistoryFileError::Write);
+        }
+
+        let mut file = match OpenOptions::new().create(true).append(true).open(&path) {
+            Err(_) => return Err(HistoryFileError::Write),
+            Ok(f) => f,
+        };
+
+        match writeln!(file, "{}", line) {
+            Err(_) => Err(HistoryFileError::Write),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    pub fn load_all_from_file() -> Result<Vec<Self>, HistoryFileError> {
+        let path = history_file_path();
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        match fs::read_to_string(&path) {
+            Err(_) => Err(HistoryFileError::Read),
+            Ok(s) => Ok(s.lines().filter_map(Self::from_line).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn record_round_trips_through_line() {
+        let record = CycleRecord::from(&FinishedCycle {
+            stance: Stance::Standing,
+            profile: "Office".to_string(),
+            started_at: SystemTime::UNIX_EPOCH + Duration::from_secs(1_790_000_000),
+            planned_duration: Duration::from_mins(15),
+            actual_duration: Duration::from_secs(7 * 60 + 30),
+            end: CycleEnd::Skip,
+            acknowledged: true,
+            reminders: 2,
+        });
+
+        let line = record.to_line().unwrap();
+        assert!(!line.contains('\n'));
+        assert!(line.contains(r#""stance":"Standing""#));
+        assert!(line.contains(r#""planned_duration_as_sec":900"#));
+        assert!(line.contains(r#""actual_duration_as_sec":450"#));
+        assert!(line.contains(r#""end":"Skip""#));
+        assert!(line.contains(r#""profile":"Office""#));
+        assert_eq!(CycleRecord::from_line(&line), Some(record));
+    }
+
+    #[test]
+    fn documented_example_line_parses() {
+        let record = CycleRecord::from_line(
+            r#"{"timestamp":"2026-10-18T09:00:00+02:00","stance":"Standing","planned_duration_as_sec":900,"actual_duration_as_sec":900,"end":"Natural","acknowledged":true,"reminders":0}"#,
+        )
+        .unwrap();
+
+        assert_eq!(record.stance, Stance::Standing);
+        assert_eq!(record.end, CycleEnd::Natural);
+        assert_eq!(record.profile, None);
+        assert!(CycleRecord::from_line("not json").is_none());
+    }
+}
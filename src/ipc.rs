@@ -0,0 +1,280 @@
+//! Control of the running app over a Unix domain socket, see [`rustnot::protocol`] for the
+//! messages.
+//!
+//! The server runs in a subscription and passes every request on to the app, which answers it
+//! through the [`Responder`] of the request. Connections are served one after another; a client
+//! has [`IPC_TIMEOUT_AS_SEC`] to send its request.
+
+use crate::paths;
+use crate::remote::{IpcRequest, Responder};
+
+use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fs, io};
+
+use async_io::Async;
+use iced::futures::channel::{mpsc, oneshot};
+use iced::futures::io::BufReader;
+use iced::futures::{AsyncBufReadExt, AsyncWriteExt, FutureExt, SinkExt, Stream};
+use rustnot::protocol::{Request, Response, fallback_socket_dir, socket_path};
+use thiserror::Error;
+
+pub const IPC_TIMEOUT_AS_SEC: u64 = 2;
+/// Waits after failing to accept a connection, doubled with every further failure, so
+/// persistent errors like running out of file descriptors do not keep the CPU busy.
+const ACCEPT_RETRY_MIN: Duration = Duration::from_millis(100);
+const ACCEPT_RETRY_MAX: Duration = Duration::from_secs(10);
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum IpcError {
+    #[error("Another instance is already listening on {0}.")]
+    InUse(String),
+    #[error("Failed to listen on {path}: {reason}")]
+    Listen { path: String, reason: String },
+}
+
+/// Requests sent to the control socket, for a subscription.
+///
+/// Emits a single error and ends if the socket cannot be bound.
+pub fn requests() -> impl Stream<Item = Result<IpcRequest, IpcError>> {
+    requests_at(socket_path())
+}
+
+fn requests_at(path: PathBuf) -> impl Stream<Item = Result<IpcRequest, IpcError>> {
+    iced::stream::channel(1, async move |mut output| {
+        let listener = match listen(&path) {
+            Err(err) => {
+                let _ = output.send(Err(err)).await;
+                return;
+            }
+            Ok(listener) => listener,
+        };
+
+        let mut retry_after = ACCEPT_RETRY_MIN;
+        loop {
+            let stream = match listener.accept().await {
+                Err(_) => {
+                    async_io::Timer::after(retry_after).await;
+                    retry_after = (retry_after * 2).min(ACCEPT_RETRY_MAX);
+                    continue;
+                }
+                Ok((stream, _)) => stream,
+            };
+            retry_after = ACCEPT_RETRY_MIN;
+            if serve(stream, &mut output).await.is_err() {
+                return;
+            }
+        }
+    })
+}
+
+/// Bind the socket at `path`, replacing a stale socket file of an instance which is gone.
+fn listen(path: &Path) -> Result<Async<UnixListener>, IpcError> {
+    let listen_error = |err: io::Error| IpcError::Listen {
+        path: path.display().to_string(),
+        reason: err.to_string(),
+    };
+
+    if path.exists() {
+        if UnixStream::connect(path).is_ok() {
+            return Err(IpcError::InUse(path.display().to_string()));
+        }
+        fs::remove_file(path).map_err(listen_error)?;
+    }
+    match path.parent() {
+        // Other users may create files in the temporary directory as well.
+        Some(dir) if dir == fallback_socket_dir() => create_private_dir(dir),
+        _ => paths::create_parent_dir(path),
+    }
+    .map_err(listen_error)?;
+    let listener = Async::<UnixListener>::bind(path).map_err(listen_error)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600)).map_err(listen_error)?;
+    Ok(listener)
+}
+
+/// Create `dir` accessible only by the user, or check that it is, if it exists already.
+fn create_private_dir(dir: &Path) -> io::Result<()> {
+    match fs::DirBuilder::new().mode(0o700).create(dir) {
+        Err(err) if err.kind() != io::ErrorKind::AlreadyExists => return Err(err),
+        _ => {}
+    }
+    let metadata = fs::symlink_metadata(dir)?;
+    if !metadata.is_dir() || metadata.permissions().mode() & 0o077 != 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!(
+                "{} is not a directory accessible only by its owner",
+                dir.display()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Read one request from `stream`, pass it on and write the response back.
+///
+/// Fails only if the app stopped listening.
+async fn serve(
+    stream: Async<UnixStream>,
+    output: &mut mpsc::Sender<Result<IpcRequest, IpcError>>,
+) -> Result<(), mpsc::SendError> {
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    let timeout = async_io::Timer::after(Duration::from_secs(IPC_TIMEOUT_AS_SEC));
+    let read = iced::futures::select! {
+        read = reader.read_line(&mut line).fuse() => read,
+        _ = FutureExt::fuse(timeout) => return Ok(()),
+    };
+    if read.is_err() {
+        return Ok(());
+    }
+
+    let response = match serde_json::from_str::<Request>(&line) {
+        Err(err) => Response::new(Err(format!("invalid request: {}", err)), None),
+        Ok(request) => {
+            let (sender, receiver) = oneshot::channel();
+            output
+                .send(Ok(IpcRequest {
+                    request,
+                    responder: Responder::new(sender),
+                }))
+                .await?;
+            match receiver.await {
+                Err(_) => return Ok(()),
+                Ok(response) => response,
+            }
+        }
+    };
+
+    let Ok(mut response_line) = serde_json::to_string(&response) else {
+        return Ok(());
+    };
+    response_line.push('\n');
+    let _ = reader.get_mut().write_all(response_line.as_bytes()).await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::{BufRead, Write};
+    use std::{env, process, sync, thread};
+
+    use iced::futures::StreamExt;
+    use rustnot::protocol::Status;
+
+    /// Serve the socket at `path` on a background thread, forwarding its requests.
+    fn spawn_server(path: &Path) -> sync::mpsc::Receiver<Result<IpcRequest, IpcError>> {
+        let (sender, receiver) = sync::mpsc::channel();
+        let stream = requests_at(path.to_path_buf());
+        thread::spawn(move || {
+            async_io::block_on(stream.for_each(|item| {
+                let _ = sender.send(item);
+                async {}
+            }))
+        });
+        receiver
+    }
+
+    fn connect(path: &Path) -> UnixStream {
+        for _ in 0..100 {
+            if let Ok(stream) = UnixStream::connect(path) {
+                return stream;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        panic!("server did not start");
+    }
+
+    fn exchange(path: &Path, request_line: &str) -> thread::JoinHandle<Response> {
+        let mut stream = connect(path);
+        let request_line = request_line.to_string();
+        thread::spawn(move || {
+            stream.write_all(request_line.as_bytes()).unwrap();
+            let mut response_line = String::new();
+            std::io::BufReader::new(stream)
+                .read_line(&mut response_line)
+                .unwrap();
+            serde_json::from_str(&response_line).unwrap()
+        })
+    }
+
+    fn temp_socket_path(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("rustnot-ipc-{}-{}.sock", name, process::id()))
+    }
+
+    #[test]
+    fn requests_are_answered_by_the_app() {
+        let path = temp_socket_path("answer");
+        // A stale socket file of a crashed instance is replaced.
+        drop(UnixListener::bind(&path).unwrap());
+        let requests = spawn_server(&path);
+
+        let client = exchange(
+            &path,
+            "{\"command\":\"set-profile\",\"profile\":\"Office\"}\n",
+        );
+        let ipc_request = requests.recv().unwrap().unwrap();
+        assert_eq!(
+            ipc_request.request,
+            Request::SetProfile {
+                profile: "Office".to_string()
+            }
+        );
+        let status = Status {
+            profile: "Office".to_string(),
+            ..Status::default()
+        };
+        ipc_request
+            .responder
+            .respond(Response::new(Ok(()), Some(status.clone())));
+
+        let response = client.join().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(response.ok);
+        assert_eq!(response.status, Some(status));
+    }
+
+    #[test]
+    fn malformed_requests_are_rejected() {
+        let path = temp_socket_path("malformed");
+        let requests = spawn_server(&path);
+
+        let response = exchange(&path, "{\"command\":\"dance\"}\n").join().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(!response.ok);
+        assert!(response.error.unwrap().starts_with("invalid request"));
+        assert!(response.status.is_none());
+        assert!(requests.try_recv().is_err());
+    }
+
+    #[test]
+    fn second_instance_does_not_take_over_socket() {
+        let path = temp_socket_path("in-use");
+        let _listener = UnixListener::bind(&path).unwrap();
+
+        let result = listen(&path);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(IpcError::InUse(_))));
+    }
+
+    #[test]
+    fn socket_dir_is_private() {
+        let dir = env::temp_dir().join(format!("rustnot-ipc-private-{}", process::id()));
+
+        let created = create_private_dir(&dir);
+        let created_mode = fs::metadata(&dir).unwrap().permissions().mode() & 0o777;
+        let existing = create_private_dir(&dir);
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o755)).unwrap();
+        let shared = create_private_dir(&dir);
+        fs::remove_dir(&dir).unwrap();
+
+        assert!(created.is_ok());
+        assert_eq!(created_mode, 0o700);
+        assert!(existing.is_ok());
+        assert_eq!(shared.unwrap_err().kind(), io::ErrorKind::PermissionDenied);
+    }
+}
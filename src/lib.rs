@@ -0,0 +1,7 @@
+//! Code shared by `rustnot` and `rustnotctl`.
+
+#[macro_use]
+extern crate serde_derive;
+
+pub mod countdown;
+pub mod protocol;
@@ -1,25 +1,88 @@
-use iced;
-
 #[macro_use]
 extern crate serde_derive;
 
+mod activity;
 mod app;
+mod cli;
 mod components;
+mod dbus;
+mod escalation;
+mod history;
+#[cfg(unix)]
+mod ipc;
+mod notifier;
+mod paths;
+mod remote;
 mod settings;
 mod settings_file;
+mod state;
+mod stats;
 mod styles;
+mod timer;
+
+use std::env;
+use std::process::ExitCode;
+
+pub fn main() -> ExitCode {
+    let cli = match cli::Cli::parse(env::args_os().skip(1)) {
+        Err(err) => {
+            eprintln!("error: {}\n\nFor more information, try '--help'.", err);
+            return ExitCode::from(cli::EXIT_USAGE);
+        }
+        Ok(cli) => cli,
+    };
+    if let Some(path) = &cli.config {
+        settings_file::override_settings_file_path(path.clone());
+    }
+
+    match cli.command {
+        cli::Command::Help => {
+            print!("{}", cli::USAGE);
+            ExitCode::SUCCESS
+        }
+        cli::Command::Version => {
+            println!("rustnot {}", env!("CARGO_PKG_VERSION"));
+            ExitCode::SUCCESS
+        }
+        cli::Command::PrintConfig => cli::print_config(&cli.overrides),
+        cli::Command::ValidateConfig => cli::validate_config(),
+        cli::Command::Run => run(cli),
+    }
+}
+
+fn run(cli: cli::Cli) -> ExitCode {
+    let loaded_settings = settings::Settings::load_from_file();
+    let window = match &loaded_settings {
+        Ok(s) => s.window,
+        Err(_) => settings::WindowGeometry::default(),
+    };
+
+    let result = iced::application(
+        move || {
+            app::App::new(
+                loaded_settings.clone(),
+                cli.overrides,
+                cli.autostart_timer,
+                cli.headless,
+            )
+        },
+        app::App::update,
+        app::App::view,
+    )
+    .subscription(app::App::subscription)
+    .theme(app::App::theme)
+    .title(app::App::title)
+    .window(iced::window::Settings {
+        visible: !cli.headless,
+        ..app::window_settings(&window)
+    })
+    .run();
 
-pub fn main() -> iced::Result {
-    iced::application(app::App::new, app::App::update, app::App::view)
-        .subscription(app::App::subscription)
-        .theme(app::App::theme)
-        .title("RustNot")
-        .window(iced::window::settings::Settings {
-            size: iced::Size {
-                width: 400.0,
-                height: 400.0,
-            },
-            ..Default::default()
-        })
-        .run()
+    match result {
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+        Ok(_) => ExitCode::SUCCESS,
+    }
 }
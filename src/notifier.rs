@@ -0,0 +1,425 @@
+use crate::settings::NotifierBackend;
+use crate::timer::CycleId;
+
+use std::fmt::Debug;
+use std::io::{self, Write};
+
+use iced::futures::channel::oneshot;
+#[cfg(not(target_os = "macos"))]
+use notify_rust::Urgency;
+use notify_rust::{Notification, Timeout};
+use thiserror::Error;
+
+const DESKTOP_TIMEOUT_AS_MS: u32 = 10 * 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastLevel {
+    Info,
+    Critical,
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastAction {
+    Done,
+    Snooze,
+    Skip,
+}
+
+impl ToastAction {
+    pub fn id(&self) -> &'static str {
+        match self {
+            ToastAction::Done => "done",
+            ToastAction::Snooze => "snooze",
+            ToastAction::Skip => "skip",
+        }
+    }
+
+    pub fn from_id(id: &str) -> Option<Self> {
+        match id {
+            "done" => Some(ToastAction::Done),
+            "snooze" => Some(ToastAction::Snooze),
+            "skip" => Some(ToastAction::Skip),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toast {
+    pub summary: String,
+    pub body: String,
+    pub level: ToastLevel,
+    pub actions: Vec<(ToastAction, String)>,
+    /// Cycle the actions refer to; they are ignored once another cycle began.
+    pub cycle_id: Option<CycleId>,
+}
+
+impl Toast {
+    pub fn info(summary: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            summary: summary.into(),
+            body: body.into(),
+            level: ToastLevel::Info,
+            actions: Vec::new(),
+            cycle_id: None,
+        }
+    }
+
+    pub fn error(summary: impl Into<String>, body: impl Into<String>) -> Self {
+        Self {
+            summary: summary.into(),
+            body: body.into(),
+            level: ToastLevel::Error,
+            actions: Vec::new(),
+            cycle_id: None,
+        }
+    }
+
+    pub fn with_action(mut self, action: ToastAction, label: impl Into<String>) -> Self {
+        self.actions.push((action, label.into()));
+        self
+    }
+
+    pub fn for_cycle(mut self, cycle_id: CycleId) -> Self {
+        self.cycle_id = Some(cycle_id);
+        self
+    }
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum NotifierError {
+    #[error("Failed to show desktop notification: {0}")]
+    Desktop(String),
+    #[error("Failed to write notification to stderr: {0}")]
+    Log(String),
+}
+
+/// Receives the action the user picked on a toast, if the backend supports actions.
+pub type PendingAction = Option<oneshot::Receiver<ToastAction>>;
+
+pub trait Notifier: Debug {
+    fn notify(&mut self, toast: &Toast) -> Result<PendingAction, NotifierError>;
+}
+
+/// Desktop notifications via the system notification daemon.
+#[derive(Debug, Default)]
+pub struct DesktopNotifier;
+
+impl Notifier for DesktopNotifier {
+    fn notify(&mut self, toast: &Toast) -> Result<PendingAction, NotifierError> {
+        let mut notification = Notification::new();
+        notification
+            .summary(&toast.summary)
+            .body(&toast.body)
+            .sound_name(match toast.level {
+                ToastLevel::Info => "dialog-information",
+                ToastLevel::Critical => "dialog-warning",
+                ToastLevel::Error => "dialog-error",
+            })
+            .timeout(Timeout::Milliseconds(DESKTOP_TIMEOUT_AS_MS));
+        #[cfg(not(target_os = "macos"))]
+        if toast.level == ToastLevel::Critical {
+            notification.urgency(Urgency::Critical);
+        }
+        for (action, label) in &toast.actions {
+            notification.action(action.id(), label);
+        }
+
+        show_desktop_notification(&notification, !toast.actions.is_empty())
+    }
+}
+
+/// Show the notification and wait for an invoked action on a separate thread.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn show_desktop_notification(
+    notification: &Notification,
+    has_actions: bool,
+) -> Result<PendingAction, NotifierError> {
+    let handle = notification
+        .show()
+        .map_err(|err| NotifierError::Desktop(err.to_string()))?;
+    if !has_actions {
+        return Ok(None);
+    }
+
+    let (sender, receiver) = oneshot::channel();
+    std::thread::spawn(move || {
+        handle.wait_for_action(|id| {
+            if let Some(action) = ToastAction::from_id(id) {
+                let _ = sender.send(action);
+            }
+        })
+    });
+    Ok(Some(receiver))
+}
+
+/// Notification actions are only supported by the XDG backend of notify-rust.
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+fn show_desktop_notification(
+    notification: &Notification,
+    _has_actions: bool,
+) -> Result<PendingAction, NotifierError> {
+    notification
+        .show()
+        .map(|_| None)
+        .map_err(|err| NotifierError::Desktop(err.to_string()))
+}
+
+/// Banner shown inside the app window until dismissed or replaced.
+///
+/// Actions of the toast are rendered as buttons by the app view.
+#[derive(Debug, Default)]
+pub struct BannerNotifier {
+    banner: Option<Toast>,
+}
+
+impl BannerNotifier {
+    pub fn current(&self) -> Option<&Toast> {
+        self.banner.as_ref()
+    }
+
+    pub fn dismiss(&mut self) {
+        self.banner = None;
+    }
+}
+
+impl Notifier for BannerNotifier {
+    fn notify(&mut self, toast: &Toast) -> Result<PendingAction, NotifierError> {
+        self.banner = Some(toast.clone());
+        Ok(None)
+    }
+}
+
+/// Plain log lines on stderr.
+#[derive(Debug, Default)]
+pub struct LogNotifier;
+
+impl Notifier for LogNotifier {
+    fn notify(&mut self, toast: &Toast) -> Result<PendingAction, NotifierError> {
+        let level = match toast.level {
+            ToastLevel::Info => "INFO",
+            ToastLevel::Critical => "CRITICAL",
+            ToastLevel::Error => "ERROR",
+        };
+        writeln!(
+            io::stderr(),
+            "[rustnot] {}: {} {}",
+            level,
+            toast.summary,
+            toast.body.replace('\n', " ")
+        )
+        .map(|_| None)
+        .map_err(|err| NotifierError::Log(err.to_string()))
+    }
+}
+
+/// Dispatches toasts to the backend chosen in the settings.
+///
+/// If the chosen backend fails, the toast is shown as in-app banner instead, which cannot fail.
+/// Without a window to show banners in, i.e. when running headless, toasts meant for the banner
+/// are logged instead.
+#[derive(Debug)]
+pub struct Notifications {
+    backend: NotifierBackend,
+    desktop: Box<dyn Notifier>,
+    log: Box<dyn Notifier>,
+    banner: BannerNotifier,
+    headless: bool,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self::new(NotifierBackend::default())
+    }
+}
+
+impl Notifications {
+    pub fn new(backend: NotifierBackend) -> Self {
+        Self {
+            backend,
+            desktop: Box::new(DesktopNotifier),
+            log: Box::new(LogNotifier),
+            banner: BannerNotifier::default(),
+            headless: false,
+        }
+    }
+
+    pub fn set_headless(&mut self, headless: bool) {
+        self.headless = headless;
+    }
+
+    pub fn set_backend(&mut self, backend: NotifierBackend) {
+        self.backend = backend;
+    }
+
+    pub fn notify(&mut self, toast: Toast) -> PendingAction {
+        let result = match self.backend {
+            NotifierBackend::Desktop => self.desktop.notify(&toast),
+            NotifierBackend::Banner if self.headless => self.log.notify(&toast),
+            NotifierBackend::Banner => self.banner.notify(&toast),
+            NotifierBackend::Log => self.log.notify(&toast),
+        };
+
+        result.unwrap_or_else(|err| {
+            let _ = self
+                .log
+                .notify(&Toast::error("Notification failed", err.to_string()));
+            if self.headless {
+                let _ = self.log.notify(&toast);
+            } else {
+                let _ = self.banner.notify(&toast);
+            }
+            None
+        })
+    }
+
+    pub fn banner(&self) -> Option<&Toast> {
+        self.banner.current()
+    }
+
+    pub fn dismiss_banner(&mut self) {
+        self.banner.dismiss();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Keeps every toast it receives in a shared list; optionally fails every call.
+    #[derive(Debug, Default, Clone)]
+    pub struct RecordingNotifier {
+        pub toasts: Rc<RefCell<Vec<Toast>>>,
+        pub fail: bool,
+    }
+
+    impl RecordingNotifier {
+        fn summaries(&self) -> Vec<String> {
+            self.toasts
+                .borrow()
+                .iter()
+                .map(|t| t.summary.clone())
+                .collect()
+        }
+    }
+
+    impl Notifier for RecordingNotifier {
+        fn notify(&mut self, toast: &Toast) -> Result<PendingAction, NotifierError> {
+            if self.fail {
+                return Err(NotifierError::Desktop("no daemon".to_string()));
+            }
+            self.toasts.borrow_mut().push(toast.clone());
+            Ok(None)
+        }
+    }
+
+    fn notifications(
+        backend: NotifierBackend,
+        desktop_fails: bool,
+    ) -> (Notifications, RecordingNotifier, RecordingNotifier) {
+        let desktop = RecordingNotifier {
+            fail: desktop_fails,
+            ..Default::default()
+        };
+        let log = RecordingNotifier::default();
+        let notifications = Notifications {
+            backend,
+            desktop: Box::new(desktop.clone()),
+            log: Box::new(log.clone()),
+            banner: BannerNotifier::default(),
+            headless: false,
+        };
+        (notifications, desktop, log)
+    }
+
+    #[test]
+    fn desktop_backend_does_not_show_banner() {
+        let (mut notifications, desktop, log) = notifications(NotifierBackend::Desktop, false);
+        notifications.notify(Toast::info("Please stand up.", "body"));
+
+        assert!(notifications.banner().is_none());
+        assert_eq!(desktop.summaries(), vec!["Please stand up."]);
+        assert!(log.summaries().is_empty());
+    }
+
+    #[test]
+    fn failing_desktop_falls_back_to_banner() {
+        let (mut notifications, _, log) = notifications(NotifierBackend::Desktop, true);
+        let toast = Toast::info("Please stand up.", "body");
+        notifications.notify(toast.clone());
+
+        assert_eq!(notifications.banner(), Some(&toast));
+        assert_eq!(log.summaries(), vec!["Notification failed"]);
+    }
+
+    #[test]
+    fn headless_logs_toasts_meant_for_banner() {
+        let (mut notifications, _, log) = notifications(NotifierBackend::Desktop, true);
+        notifications.set_headless(true);
+        notifications.notify(Toast::info("Please stand up.", "body"));
+        notifications.set_backend(NotifierBackend::Banner);
+        notifications.notify(Toast::info("Please sit down.", "body"));
+
+        assert!(notifications.banner().is_none());
+        assert_eq!(
+            log.summaries(),
+            vec![
+                "Notification failed",
+                "Please stand up.",
+                "Please sit down."
+            ]
+        );
+    }
+
+    #[test]
+    fn banner_backend_keeps_latest_toast_until_dismissed() {
+        let (mut notifications, desktop, _) = notifications(NotifierBackend::Banner, false);
+        notifications.notify(Toast::info("first", ""));
+        notifications.notify(Toast::error("second", ""));
+
+        assert_eq!(notifications.banner().unwrap().summary, "second");
+        assert!(desktop.summaries().is_empty());
+        notifications.dismiss_banner();
+        assert!(notifications.banner().is_none());
+    }
+
+    #[test]
+    fn banner_keeps_toast_actions() {
+        let (mut notifications, _, _) = notifications(NotifierBackend::Banner, false);
+        let toast = Toast::info("Please stand up.", "")
+            .with_action(ToastAction::Done, "Done")
+            .with_action(ToastAction::Skip, "Skip");
+
+        assert!(notifications.notify(toast).is_none());
+        let actions: Vec<_> = notifications
+            .banner()
+            .unwrap()
+            .actions
+            .iter()
+            .map(|(action, _)| *action)
+            .collect();
+        assert_eq!(actions, vec![ToastAction::Done, ToastAction::Skip]);
+    }
+
+    #[test]
+    fn action_ids_round_trip() {
+        for action in [ToastAction::Done, ToastAction::Snooze, ToastAction::Skip] {
+            assert_eq!(ToastAction::from_id(action.id()), Some(action));
+        }
+        assert_eq!(ToastAction::from_id("__closed"), None);
+    }
+
+    #[test]
+    fn switching_backend_applies_to_next_toast() {
+        let (mut notifications, _, log) = notifications(NotifierBackend::Banner, false);
+        notifications.set_backend(NotifierBackend::Log);
+        notifications.notify(Toast::info("logged", ""));
+
+        assert!(notifications.banner().is_none());
+        assert_eq!(log.summaries(), vec!["logged"]);
+    }
+}
@@ -0,0 +1,186 @@
+//! Locations of the files rustnot reads and writes.
+//!
+//! The settings file is searched in this order:
+//!
+//! 1. `rustnot_config.toml` in the current working directory, if it exists
+//! 2. `$XDG_CONFIG_HOME/rustnot/rustnot_config.toml`
+//! 3. `~/.config/rustnot/rustnot_config.toml`, if `$XDG_CONFIG_HOME` is unset
+//!
+//! The history file lives in `$XDG_DATA_HOME/rustnot/` (falling back to
+//! `~/.local/share/rustnot/`) instead.
+//!
+//! Per XDG base directory specification, empty or relative `$XDG_*` values are ignored. If
+//! no directory can be determined, the current working directory is used.
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::{env, fs, io, process};
+
+pub const APP_DIR_NAME: &str = "rustnot";
+
+pub fn config_dir() -> Option<PathBuf> {
+    resolve_base_dir(
+        env::var_os("XDG_CONFIG_HOME"),
+        env::var_os("HOME"),
+        ".config",
+    )
+    .map(|dir| dir.join(APP_DIR_NAME))
+}
+
+pub fn data_dir() -> Option<PathBuf> {
+    resolve_base_dir(
+        env::var_os("XDG_DATA_HOME"),
+        env::var_os("HOME"),
+        ".local/share",
+    )
+    .map(|dir| dir.join(APP_DIR_NAME))
+}
+
+/// Path of the settings file following the documented search order.
+pub fn settings_file_path(file_name: &str) -> PathBuf {
+    let cwd_file = PathBuf::from(file_name);
+    search_file(&cwd_file, config_dir(), file_name)
+}
+
+/// Path of the history file in the data directory.
+pub fn history_file_path(file_name: &str) -> PathBuf {
+    match data_dir() {
+        Some(dir) => dir.join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+/// Create the parent directory of `path`, if it does not exist yet.
+pub fn create_parent_dir(path: &Path) -> io::Result<()> {
+    match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => fs::create_dir_all(parent),
+        _ => Ok(()),
+    }
+}
+
+/// Path with `suffix` appended to the file name.
+pub fn with_file_name_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(suffix);
+    path.with_file_name(file_name)
+}
+
+/// Replace the file at `path` with `contents`, so it holds either the old or the new contents
+/// even if writing fails halfway.
+///
+/// The contents are written to a temporary file in the same directory, synced to disk and then
+/// renamed over `path`.
+pub fn write_atomically(path: &Path, contents: &str) -> io::Result<()> {
+    create_parent_dir(path)?;
+    let temp_path = with_file_name_suffix(path, &format!(".{}.tmp", process::id()));
+
+    let result = File::create(&temp_path).and_then(|mut file| {
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()
+    });
+    let result = result.and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+        return result;
+    }
+
+    // Persist the rename itself; not every platform can sync a directory.
+    if let Some(dir) = path.parent()
+        && let Ok(dir) = File::open(if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        })
+    {
+        let _ = dir.sync_all();
+    }
+    Ok(())
+}
+
+fn resolve_base_dir(
+    xdg_dir: Option<OsString>,
+    home: Option<OsString>,
+    home_fallback: &str,
+) -> Option<PathBuf> {
+    if let Some(xdg_dir) = xdg_dir.map(PathBuf::from)
+        && xdg_dir.is_absolute()
+    {
+        return Some(xdg_dir);
+    }
+
+    home.map(PathBuf::from)
+        .filter(|home| home.is_absolute())
+        .map(|home| home.join(home_fallback))
+}
+
+fn search_file(cwd_file: &Path, dir: Option<PathBuf>, file_name: &str) -> PathBuf {
+    if cwd_file.exists() {
+        return cwd_file.to_path_buf();
+    }
+
+    match dir {
+        Some(dir) => dir.join(file_name),
+        None => cwd_file.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xdg_dir_takes_precedence_over_home() {
+        assert_eq!(
+            resolve_base_dir(
+                Some("/xdg/config".into()),
+                Some("/home/me".into()),
+                ".config"
+            ),
+            Some(PathBuf::from("/xdg/config"))
+        );
+    }
+
+    #[test]
+    fn empty_or_relative_xdg_dir_falls_back_to_home() {
+        for xdg_dir in ["", "relative/config"] {
+            assert_eq!(
+                resolve_base_dir(Some(xdg_dir.into()), Some("/home/me".into()), ".config"),
+                Some(PathBuf::from("/home/me/.config"))
+            );
+        }
+        assert_eq!(resolve_base_dir(None, None, ".config"), None);
+    }
+
+    #[test]
+    fn atomic_write_replaces_file_without_leftovers() {
+        let dir = env::temp_dir().join(format!("rustnot-atomic-{}", process::id()));
+        let path = dir.join("file.toml");
+
+        write_atomically(&path, "first").unwrap();
+        write_atomically(&path, "second").unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        let entries = fs::read_dir(&dir).unwrap().count();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(contents, "second");
+        assert_eq!(entries, 1);
+    }
+
+    #[test]
+    fn existing_file_in_cwd_overrides_config_dir() {
+        let cwd_file = env::temp_dir().join(format!("rustnot-paths-{}.toml", std::process::id()));
+        let config_dir = Some(PathBuf::from("/xdg/config/rustnot"));
+
+        assert_eq!(
+            search_file(&cwd_file, config_dir.clone(), "rustnot_config.toml"),
+            PathBuf::from("/xdg/config/rustnot/rustnot_config.toml")
+        );
+
+        fs::write(&cwd_file, "").unwrap();
+        let found = search_file(&cwd_file, config_dir, "rustnot_config.toml");
+        fs::remove_file(&cwd_file).unwrap();
+        assert_eq!(found, cwd_file);
+    }
+}
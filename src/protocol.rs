@@ -0,0 +1,117 @@
+//! Messages exchanged over the control socket, shared with `rustnotctl`.
+//!
+//! A client connects, writes one request as a line of JSON and reads one response line back,
+//! then the connection is closed. Examples:
+//!
+//! ```text
+//! > {"command":"set-profile","profile":"Office"}
+//! < {"ok":true,"status":{"running":true,"paused":false,"awaiting_confirmation":false,"stance":"Sitting","remaining_as_sec":2400,"duration_as_sec":2400,"profile":"Office"}}
+//! > {"command":"pause"}
+//! < {"ok":false,"error":"the timer is not running","status":{...}}
+//! ```
+//!
+//! Responses carry the status after the command ran. Only responses to malformed requests lack
+//! the status.
+
+use std::env;
+use std::path::PathBuf;
+
+pub const SOCKET_FILE_NAME: &str = "rustnot.sock";
+/// Environment variable overriding the socket path, for the app and `rustnotctl` alike.
+pub const SOCKET_PATH_ENV: &str = "RUSTNOT_SOCKET";
+
+/// Path of the control socket.
+///
+/// `$RUSTNOT_SOCKET` if set, else `rustnot.sock` in `$XDG_RUNTIME_DIR`, falling back to
+/// `rustnot.sock` in [`fallback_socket_dir`].
+pub fn socket_path() -> PathBuf {
+    if let Some(path) = env::var_os(SOCKET_PATH_ENV).filter(|path| !path.is_empty()) {
+        return PathBuf::from(path);
+    }
+    match env::var_os("XDG_RUNTIME_DIR").map(PathBuf::from) {
+        Some(dir) if dir.is_absolute() => dir.join(SOCKET_FILE_NAME),
+        _ => fallback_socket_dir().join(SOCKET_FILE_NAME),
+    }
+}
+
+/// Directory `rustnot-$USER` in the temporary directory, for the socket without
+/// `$XDG_RUNTIME_DIR`. Only its owner may access it.
+pub fn fallback_socket_dir() -> PathBuf {
+    env::temp_dir().join(format!("rustnot-{}", env::var("USER").unwrap_or_default()))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum Request {
+    Start,
+    Stop,
+    Pause,
+    Resume,
+    /// End the current cycle and start the next one right away.
+    Skip,
+    Status,
+    SetProfile {
+        profile: String,
+    },
+    /// Exit the app, e.g. one running headless. The response is sent before it exits.
+    Quit,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<Status>,
+}
+
+impl Response {
+    /// Response with the outcome of a command and the status after it.
+    pub fn new(result: Result<(), String>, status: Option<Status>) -> Self {
+        Self {
+            ok: result.is_ok(),
+            error: result.err(),
+            status,
+        }
+    }
+}
+
+/// State of the timer as shown in the window.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Status {
+    pub running: bool,
+    pub paused: bool,
+    pub awaiting_confirmation: bool,
+    /// `Sitting` or `Standing`, while the timer runs.
+    pub stance: Option<String>,
+    /// Time until the next stance change, while the timer runs.
+    pub remaining_as_sec: Option<u64>,
+    /// Full duration of the current cycle, while the timer runs.
+    pub duration_as_sec: Option<u64>,
+    pub profile: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_use_command_tag() {
+        assert_eq!(
+            serde_json::to_string(&Request::SetProfile {
+                profile: "Office".to_string()
+            })
+            .unwrap(),
+            "{\"command\":\"set-profile\",\"profile\":\"Office\"}"
+        );
+        assert_eq!(
+            serde_json::from_str::<Request>("{\"command\":\"skip\"}").unwrap(),
+            Request::Skip
+        );
+        assert_eq!(
+            serde_json::from_str::<Request>("{\"command\":\"quit\"}").unwrap(),
+            Request::Quit
+        );
+    }
+}
@@ -0,0 +1,34 @@
+//! Requests of remote control clients, received over the control socket, see
+//! [`ipc`](crate::ipc), or D-Bus, see [`dbus`](crate::dbus).
+
+use std::sync::{Arc, Mutex};
+
+use iced::futures::channel::oneshot;
+use rustnot::protocol::{Request, Response};
+
+/// Time between answering a `quit` request and exiting, for the response to be sent.
+pub const QUIT_DELAY_AS_MS: u64 = 200;
+
+/// Answers a request; only the first response is sent.
+#[derive(Debug, Clone)]
+pub struct Responder(Arc<Mutex<Option<oneshot::Sender<Response>>>>);
+
+impl Responder {
+    pub fn new(sender: oneshot::Sender<Response>) -> Self {
+        Self(Arc::new(Mutex::new(Some(sender))))
+    }
+
+    pub fn respond(&self, response: Response) {
+        if let Ok(mut sender) = self.0.lock()
+            && let Some(sender) = sender.take()
+        {
+            let _ = sender.send(response);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IpcRequest {
+    pub request: Request,
+    pub responder: Responder,
+}
@@ -1,29 +1,431 @@
-#[derive(Debug, Clone, Copy, Deserialize, Serialize)]
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+/// Version of the settings file format, see `settings_file` for the migrations.
+pub const SCHEMA_VERSION: u32 = 2;
+
+pub const DEFAULT_PROFILE_NAME: &str = "Default";
+
+/// Missing fields are filled with their default value.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
 pub struct Settings {
+    pub schema_version: u32,
+    pub active_profile: String,
+    pub notifier_backend: NotifierBackend,
+    pub snooze_duration_as_min: u64,
+    pub confirm_stance_change: bool,
+    pub daily_standing_goal_as_min: u64,
+    pub running_cycle_on_reload: RunningCyclePolicy,
+    pub running_cycle_on_restart: RestartPolicy,
+    pub wake_policy: WakePolicy,
+    pub auto_pause: AutoPause,
+    pub theme: ThemeChoice,
+    pub escalation: EscalationPolicy,
+    pub window: WindowGeometry,
+    pub profiles: BTreeMap<String, Profile>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION,
+            active_profile: DEFAULT_PROFILE_NAME.to_string(),
+            notifier_backend: NotifierBackend::default(),
+            snooze_duration_as_min: 5,
+            confirm_stance_change: false,
+            daily_standing_goal_as_min: 120,
+            running_cycle_on_reload: RunningCyclePolicy::default(),
+            running_cycle_on_restart: RestartPolicy::default(),
+            wake_policy: WakePolicy::default(),
+            auto_pause: AutoPause::default(),
+            theme: ThemeChoice::default(),
+            escalation: EscalationPolicy::default(),
+            window: WindowGeometry::default(),
+            profiles: BTreeMap::from([(DEFAULT_PROFILE_NAME.to_string(), Profile::default())]),
+        }
+    }
+}
+
+/// Durations and start stance of one desk setup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Profile {
     pub sit_duration_as_min: u64,
     pub stand_duration_as_min: u64,
     pub start_stance: Stance,
 }
 
-impl Default for Settings {
+impl Default for Profile {
     fn default() -> Self {
         Self {
             sit_duration_as_min: 40,
             stand_duration_as_min: 20,
-            start_stance: Stance::default(),
+            start_stance: Stance::Sitting,
         }
     }
 }
 
 impl Settings {
+    /// The active profile, or the default values if it does not exist.
+    pub fn profile(&self) -> Profile {
+        self.profiles
+            .get(&self.active_profile)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// The active profile, created with default values if it does not exist.
+    pub fn profile_mut(&mut self) -> &mut Profile {
+        self.profiles
+            .entry(self.active_profile.clone())
+            .or_default()
+    }
+
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    /// Make the profile `name` the active one, if it exists.
+    pub fn select_profile(&mut self, name: &str) -> bool {
+        if !self.profiles.contains_key(name) {
+            return false;
+        }
+        self.active_profile = name.to_string();
+        true
+    }
+
+    /// Add a copy of the active profile under a new name and make it the active one.
+    pub fn create_profile(&mut self) -> String {
+        let name = (self.profiles.len() + 1..)
+            .map(|n| format!("Profile {}", n))
+            .find(|name| !self.profiles.contains_key(name))
+            .unwrap_or_default();
+        self.profiles.insert(name.clone(), self.profile());
+        self.active_profile = name.clone();
+        name
+    }
+
+    pub fn rename_profile(&mut self, new_name: &str) -> Result<(), String> {
+        let new_name = new_name.trim();
+        if new_name == self.active_profile {
+            return Ok(());
+        }
+        check_profile_name(new_name)?;
+        if self.profiles.contains_key(new_name) {
+            return Err("a profile with this name already exists".to_string());
+        }
+
+        let profile = self
+            .profiles
+            .remove(&self.active_profile)
+            .unwrap_or_default();
+        self.profiles.insert(new_name.to_string(), profile);
+        self.active_profile = new_name.to_string();
+        Ok(())
+    }
+
+    /// Remove the active profile and activate the first remaining one.
+    ///
+    /// The last profile cannot be deleted.
+    pub fn delete_profile(&mut self) -> bool {
+        if self.profiles.len() <= 1 {
+            return false;
+        }
+        self.profiles.remove(&self.active_profile);
+        self.active_profile = self.profiles.keys().next().cloned().unwrap_or_default();
+        true
+    }
+
     pub fn get_duration_for_stance(&self, stance: &Stance) -> u64 {
+        let profile = self.profile();
         match stance {
-            Stance::Sitting => self.sit_duration_as_min,
-            Stance::Standing => self.stand_duration_as_min,
+            Stance::Sitting => profile.sit_duration_as_min,
+            Stance::Standing => profile.stand_duration_as_min,
+        }
+    }
+
+    /// Check the settings for values the timer cannot work with.
+    ///
+    /// Returns one error per invalid field.
+    pub fn validate(&self) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        let mut check_range =
+            |field: SettingsField, profile: Option<&str>, value: u64, min: u64, max: u64| {
+                let message = if value < min {
+                    format!("must be at least {} min", min)
+                } else if value > max {
+                    format!("must be at most {} min", max)
+                } else {
+                    return;
+                };
+                errors.push(FieldError {
+                    field,
+                    profile: profile.map(str::to_string),
+                    message,
+                });
+            };
+
+        for (name, profile) in &self.profiles {
+            check_range(
+                SettingsField::SitDuration,
+                Some(name),
+                profile.sit_duration_as_min,
+                1,
+                MAX_DURATION_AS_MIN,
+            );
+            check_range(
+                SettingsField::StandDuration,
+                Some(name),
+                profile.stand_duration_as_min,
+                1,
+                MAX_DURATION_AS_MIN,
+            );
+        }
+        check_range(
+            SettingsField::SnoozeDuration,
+            None,
+            self.snooze_duration_as_min,
+            1,
+            MAX_SNOOZE_DURATION_AS_MIN,
+        );
+        check_range(
+            SettingsField::DailyStandingGoal,
+            None,
+            self.daily_standing_goal_as_min,
+            0,
+            MAX_DURATION_AS_MIN,
+        );
+        check_range(
+            SettingsField::EscalationRepeatAfter,
+            None,
+            self.escalation.repeat_after_as_min,
+            1,
+            MAX_DURATION_AS_MIN,
+        );
+        check_range(
+            SettingsField::EscalationMaxRepeatInterval,
+            None,
+            self.escalation.max_repeat_interval_as_min,
+            self.escalation.repeat_after_as_min,
+            MAX_DURATION_AS_MIN,
+        );
+        check_range(
+            SettingsField::AutoPauseIdleThreshold,
+            None,
+            self.auto_pause.idle_threshold_as_min,
+            1,
+            MAX_DURATION_AS_MIN,
+        );
+
+        for (field, size) in [
+            (SettingsField::WindowWidth, self.window.width),
+            (SettingsField::WindowHeight, self.window.height),
+        ] {
+            if size < MIN_WINDOW_SIZE {
+                errors.push(FieldError {
+                    field,
+                    profile: None,
+                    message: format!("must be at least {} px", MIN_WINDOW_SIZE),
+                });
+            }
+        }
+
+        for name in self.profiles.keys() {
+            if let Err(message) = check_profile_name(name) {
+                errors.push(FieldError {
+                    field: SettingsField::ProfileName,
+                    profile: Some(name.clone()),
+                    message,
+                });
+            }
+        }
+        if !self.profiles.contains_key(&self.active_profile) {
+            errors.push(FieldError {
+                field: SettingsField::ActiveProfile,
+                profile: None,
+                message: format!("there is no profile named \"{}\"", self.active_profile),
+            });
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+fn check_profile_name(name: &str) -> Result<(), String> {
+    if name.trim().is_empty() {
+        Err("must not be empty".to_string())
+    } else if name.trim() != name {
+        Err("must not start or end with whitespace".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+pub const MAX_DURATION_AS_MIN: u64 = 24 * 60;
+pub const MAX_SNOOZE_DURATION_AS_MIN: u64 = 120;
+pub const MIN_WINDOW_SIZE: u32 = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsField {
+    ActiveProfile,
+    ProfileName,
+    SitDuration,
+    StandDuration,
+    SnoozeDuration,
+    DailyStandingGoal,
+    EscalationRepeatAfter,
+    EscalationMaxRepeatInterval,
+    WindowWidth,
+    WindowHeight,
+    AutoPauseIdleThreshold,
+}
+
+impl SettingsField {
+    /// Key of the field in the settings file, relative to its profile for profile fields.
+    pub fn key(&self) -> &'static str {
+        match self {
+            SettingsField::ActiveProfile => "active_profile",
+            SettingsField::ProfileName => "name",
+            SettingsField::SitDuration => "sit_duration_as_min",
+            SettingsField::StandDuration => "stand_duration_as_min",
+            SettingsField::SnoozeDuration => "snooze_duration_as_min",
+            SettingsField::DailyStandingGoal => "daily_standing_goal_as_min",
+            SettingsField::EscalationRepeatAfter => "escalation.repeat_after_as_min",
+            SettingsField::EscalationMaxRepeatInterval => "escalation.max_repeat_interval_as_min",
+            SettingsField::WindowWidth => "window.width",
+            SettingsField::WindowHeight => "window.height",
+            SettingsField::AutoPauseIdleThreshold => "auto_pause.idle_threshold_as_min",
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{}: {message}", self.key())]
+pub struct FieldError {
+    pub field: SettingsField,
+    /// Profile the field belongs to, for fields of a profile.
+    pub profile: Option<String>,
+    pub message: String,
+}
+
+impl FieldError {
+    /// Dotted key of the field in the settings file.
+    pub fn key(&self) -> String {
+        match &self.profile {
+            Some(profile) => format!("profiles.\"{}\".{}", profile, self.field.key()),
+            None => self.field.key().to_string(),
+        }
+    }
+}
+
+/// How to repeat and escalate the notification of an ignored stance change.
+///
+/// Reminders are always sent while a stance change awaits confirmation, even if the policy
+/// itself is not enabled. They are only made critical or raise the window if it is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct EscalationPolicy {
+    pub enabled: bool,
+    pub repeat_after_as_min: u64,
+    pub max_repeat_interval_as_min: u64,
+    pub critical_from_step: u32,
+    pub raise_window_from_step: u32,
+}
+
+impl Default for EscalationPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            repeat_after_as_min: 1,
+            max_repeat_interval_as_min: 16,
+            critical_from_step: 2,
+            raise_window_from_step: 3,
+        }
+    }
+}
+
+/// Pausing the timer while the user is away from the desk.
+///
+/// The user counts as away while the session is locked or after no input for the idle
+/// threshold. A cycle paused this way is resumed when the user returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct AutoPause {
+    pub enabled: bool,
+    pub idle_threshold_as_min: u64,
+    pub backend: ActivityBackend,
+}
+
+impl Default for AutoPause {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_threshold_as_min: 5,
+            backend: ActivityBackend::default(),
+        }
+    }
+}
+
+/// Where the lock state and idle time come from, see `activity`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+pub enum ActivityBackend {
+    /// logind, together with GNOME's idle monitor on Wayland or X11 otherwise.
+    #[default]
+    Auto,
+    Logind,
+    X11,
+    Mutter,
+}
+
+/// Size, position and stacking of the main window.
+///
+/// Sizes and positions are logical pixels. Without a position the window manager places the
+/// window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct WindowGeometry {
+    pub width: u32,
+    pub height: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<i32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub y: Option<i32>,
+    pub always_on_top: bool,
+}
+
+impl Default for WindowGeometry {
+    fn default() -> Self {
+        Self {
+            width: 480,
+            height: 440,
+            x: None,
+            y: None,
+            always_on_top: false,
         }
     }
 }
 
+impl WindowGeometry {
+    pub fn position(&self) -> Option<(i32, i32)> {
+        self.x.zip(self.y)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ThemeChoice {
+    /// Follow the light or dark mode of the system.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Stance {
     #[default]
@@ -39,3 +441,192 @@ pub fn inverted(current: Stance) -> Self {
         }
     }
 }
+
+/// What happens to the running cycle when the settings file is reloaded.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RunningCyclePolicy {
+    /// The running cycle keeps its duration, new durations apply from the next cycle on.
+    #[default]
+    Keep,
+    /// The running cycle gets the new duration of its stance, keeping the time it already ran.
+    Recalculate,
+}
+
+/// What happens to the cycle, which was running when the app was closed, on the next start.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum RestartPolicy {
+    /// The cycle continues, counting the time the app was closed unless it was paused.
+    #[default]
+    Continue,
+    /// The stance of the cycle starts over with its full duration.
+    Fresh,
+    /// The user decides between continuing and starting fresh.
+    Ask,
+}
+
+/// What happens to the running cycle when the system wakes up from suspend.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum WakePolicy {
+    /// The time asleep counts as spent in the cycle, unless it was paused or awaited
+    /// confirmation.
+    #[default]
+    Continue,
+    /// The time asleep counts as a pause: the cycle goes on where it was.
+    Break,
+    /// The cycle ends and a new one starts with the start stance.
+    Restart,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum NotifierBackend {
+    #[default]
+    Desktop,
+    Banner,
+    Log,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid_fields(settings: &Settings) -> Vec<SettingsField> {
+        settings
+            .validate()
+            .unwrap_err()
+            .into_iter()
+            .map(|err| err.field)
+            .collect()
+    }
+
+    fn with_profile(sit: u64, stand: u64) -> Settings {
+        let mut settings = Settings::default();
+        *settings.profile_mut() = Profile {
+            sit_duration_as_min: sit,
+            stand_duration_as_min: stand,
+            start_stance: Stance::Sitting,
+        };
+        settings
+    }
+
+    #[test]
+    fn default_settings_are_valid() {
+        assert_eq!(Settings::default().validate(), Ok(()));
+    }
+
+    #[test]
+    fn zero_durations_are_rejected() {
+        let settings = Settings {
+            snooze_duration_as_min: 0,
+            ..with_profile(0, 0)
+        };
+
+        assert_eq!(
+            invalid_fields(&settings),
+            vec![
+                SettingsField::SitDuration,
+                SettingsField::StandDuration,
+                SettingsField::SnoozeDuration
+            ]
+        );
+        assert_eq!(
+            settings.validate().unwrap_err()[0].to_string(),
+            "profiles.\"Default\".sit_duration_as_min: must be at least 1 min"
+        );
+    }
+
+    #[test]
+    fn durations_longer_than_a_day_are_rejected() {
+        let settings = Settings {
+            daily_standing_goal_as_min: MAX_DURATION_AS_MIN + 1,
+            ..with_profile(40, MAX_DURATION_AS_MIN + 1)
+        };
+
+        assert_eq!(
+            invalid_fields(&settings),
+            vec![
+                SettingsField::StandDuration,
+                SettingsField::DailyStandingGoal
+            ]
+        );
+    }
+
+    #[test]
+    fn tiny_window_is_rejected() {
+        let mut settings = Settings::default();
+        settings.window.width = MIN_WINDOW_SIZE - 1;
+        settings.window.height = 0;
+
+        assert_eq!(
+            invalid_fields(&settings),
+            vec![SettingsField::WindowWidth, SettingsField::WindowHeight]
+        );
+        assert_eq!(
+            settings.validate().unwrap_err()[0].to_string(),
+            format!("window.width: must be at least {} px", MIN_WINDOW_SIZE)
+        );
+    }
+
+    #[test]
+    fn escalation_interval_cap_must_not_be_below_first_repetition() {
+        let mut settings = Settings::default();
+        settings.escalation.repeat_after_as_min = 10;
+        settings.escalation.max_repeat_interval_as_min = 5;
+
+        assert_eq!(
+            invalid_fields(&settings),
+            vec![SettingsField::EscalationMaxRepeatInterval]
+        );
+    }
+
+    #[test]
+    fn auto_pause_needs_idle_threshold() {
+        let mut settings = Settings::default();
+        settings.auto_pause.idle_threshold_as_min = 0;
+
+        assert_eq!(
+            invalid_fields(&settings),
+            vec![SettingsField::AutoPauseIdleThreshold]
+        );
+        assert_eq!(
+            settings.validate().unwrap_err()[0].to_string(),
+            "auto_pause.idle_threshold_as_min: must be at least 1 min"
+        );
+    }
+
+    #[test]
+    fn active_profile_must_exist() {
+        let settings = Settings {
+            active_profile: "Office".to_string(),
+            ..Settings::default()
+        };
+
+        assert_eq!(
+            invalid_fields(&settings),
+            vec![SettingsField::ActiveProfile]
+        );
+    }
+
+    #[test]
+    fn profiles_can_be_created_renamed_and_deleted() {
+        let mut settings = with_profile(50, 10);
+
+        assert_eq!(settings.create_profile(), "Profile 2");
+        assert_eq!(settings.profile().sit_duration_as_min, 50);
+        settings.profile_mut().sit_duration_as_min = 30;
+
+        assert!(settings.rename_profile(" ").is_err());
+        assert!(settings.rename_profile(DEFAULT_PROFILE_NAME).is_err());
+        assert_eq!(settings.rename_profile("Home office"), Ok(()));
+        assert_eq!(settings.profile_names(), vec!["Default", "Home office"]);
+        assert_eq!(settings.get_duration_for_stance(&Stance::Sitting), 30);
+
+        assert!(settings.select_profile(DEFAULT_PROFILE_NAME));
+        assert!(!settings.select_profile("Recovery"));
+        assert_eq!(settings.get_duration_for_stance(&Stance::Sitting), 50);
+
+        assert!(settings.delete_profile());
+        assert_eq!(settings.active_profile, "Home office");
+        assert!(!settings.delete_profile());
+        assert_eq!(settings.validate(), Ok(()));
+    }
+}
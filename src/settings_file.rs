@@ -1,58 +1,1071 @@
-use crate::settings;
+use crate::paths;
+use crate::settings::{self, FieldError, SCHEMA_VERSION};
 
-use std::fs::{self, File};
-use std::io::Write;
-use std::path::Path;
+use std::fmt::{self, Display};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::SystemTime;
+use std::{fs, io};
 
 use thiserror::Error;
+use toml_edit::{DocumentMut, Item, TableLike};
 
 pub const SETTINGS_FILE_NAME: &str = "rustnot_config.toml";
+pub const SETTINGS_FILE_CHECK_INTERVAL_AS_SEC: u64 = 2;
+/// Delay before a changed theme or window geometry is saved, to save a drag only once.
+pub const WINDOW_STATE_SAVE_DELAY_AS_SEC: u64 = 2;
+/// Keys of the settings, which change without confirming the settings modal.
+const WINDOW_STATE_KEYS: [&str; 2] = ["theme", "window"];
+/// Keys of the settings, which are left out when saving while they are unset. Saving removes
+/// them from the file then, unlike keys rustnot does not know.
+const OPTIONAL_KEYS: [&str; 2] = ["window.x", "window.y"];
+/// Tables of the settings, whose keys are names chosen by the user, e.g. of profiles. All their
+/// entries belong to rustnot, so deleted and renamed entries are removed from the file.
+const MAP_TABLES: [&str; 1] = ["profiles"];
 
 #[derive(Error, Debug, Clone)]
 pub enum SettingsFileError {
-    #[error("Failed to read the settings file.")]
-    Read,
-    #[error("Failed to write the settings file.")]
-    Write,
-    #[error("File does not exist.")]
-    MissingFile,
-    #[error("Failed to parse the settings file.")]
-    ParseFile,
-    #[error("Failed to parse the settings state.")]
-    ParseState,
+    #[error("Failed to read the settings file {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: Arc<io::Error>,
+    },
+    #[error("Failed to write the settings file {}: {source}", path.display())]
+    Write {
+        path: PathBuf,
+        source: Arc<io::Error>,
+    },
+    #[error("File does not exist: {}", path.display())]
+    MissingFile { path: PathBuf },
+    #[error(
+        "Failed to parse the settings file {}{}: {}",
+        path.display(),
+        location.as_ref().map(|l| format!(" at {}", l)).unwrap_or_default(),
+        source.message()
+    )]
+    ParseFile {
+        path: PathBuf,
+        location: Option<ParseLocation>,
+        source: Box<toml::de::Error>,
+    },
+    #[error(
+        "Invalid settings in {}: {}",
+        path.display(),
+        errors.iter().map(ToString::to_string).collect::<Vec<_>>().join(", ")
+    )]
+    Invalid {
+        path: PathBuf,
+        errors: Vec<FieldError>,
+    },
+    #[error(
+        "Unsupported schema version {} in {}, this rustnot supports up to {}",
+        version.as_deref().unwrap_or("?"),
+        path.display(),
+        SCHEMA_VERSION
+    )]
+    UnsupportedVersion {
+        path: PathBuf,
+        version: Option<String>,
+    },
+    #[error("Failed to parse the settings state: {source}")]
+    ParseState { source: toml::ser::Error },
+}
+
+impl SettingsFileError {
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            SettingsFileError::Read { path, .. }
+            | SettingsFileError::Write { path, .. }
+            | SettingsFileError::MissingFile { path }
+            | SettingsFileError::ParseFile { path, .. }
+            | SettingsFileError::Invalid { path, .. }
+            | SettingsFileError::UnsupportedVersion { path, .. } => Some(path),
+            SettingsFileError::ParseState { .. } => None,
+        }
+    }
+
+    /// Labeled details for showing the error to the user.
+    pub fn details(&self) -> Vec<(&'static str, String)> {
+        let mut details = Vec::new();
+        if let Some(path) = self.path() {
+            details.push(("File", path.display().to_string()));
+        }
+        match self {
+            SettingsFileError::Read { source, .. } | SettingsFileError::Write { source, .. } => {
+                details.push(("Error", source.to_string()));
+            }
+            SettingsFileError::MissingFile { .. } => {
+                details.push(("Error", "File does not exist".to_string()));
+            }
+            SettingsFileError::ParseFile {
+                location, source, ..
+            } => {
+                if let Some(location) = location {
+                    details.push((
+                        "Location",
+                        format!("line {}, column {}", location.line, location.column),
+                    ));
+                    if let Some(key) = &location.key {
+                        details.push(("Key", key.clone()));
+                    }
+                }
+                details.push(("Error", source.message().to_string()));
+            }
+            SettingsFileError::Invalid { errors, .. } => {
+                for err in errors {
+                    details.push(("Invalid", err.to_string()));
+                }
+            }
+            SettingsFileError::UnsupportedVersion { version, .. } => {
+                details.push((
+                    "Error",
+                    format!(
+                        "Unsupported schema version {}, expected at most {}",
+                        version.as_deref().unwrap_or("?"),
+                        SCHEMA_VERSION
+                    ),
+                ));
+            }
+            SettingsFileError::ParseState { source } => {
+                details.push(("Error", source.to_string()));
+            }
+        }
+        details
+    }
+}
+
+/// Position of a parse error in the settings file.
+///
+/// Line and column start at 1. The key is the dotted key of the `key = value` line the error
+/// points to, prefixed with the surrounding table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseLocation {
+    pub line: usize,
+    pub column: usize,
+    pub key: Option<String>,
+}
+
+impl ParseLocation {
+    fn from_span(source: &str, span: Range<usize>) -> Self {
+        let offset = span.start.min(source.len());
+        let before = &source[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let line_end = source[offset..]
+            .find('\n')
+            .map_or(source.len(), |i| offset + i);
+
+        let table = before
+            .lines()
+            .map(str::trim)
+            .rfind(|l| l.starts_with('[') && l.ends_with(']'))
+            .map(|l| l.trim_matches(['[', ']']).trim().to_string());
+        let key = source[line_start..line_end]
+            .split_once('=')
+            .map(|(key, _)| key.trim().to_string())
+            .filter(|key| !key.is_empty() && !key.starts_with('#'))
+            .map(|key| match &table {
+                Some(table) => format!("{}.{}", table, key),
+                None => key,
+            });
+
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            key,
+        }
+    }
+}
+
+impl Display for ParseLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)?;
+        if let Some(key) = &self.key {
+            write!(f, " (key `{}`)", key)?;
+        }
+        Ok(())
+    }
+}
+
+/// Path of the settings file, see [`paths`] for the search order.
+static SETTINGS_FILE_PATH_OVERRIDE: OnceLock<PathBuf> = OnceLock::new();
+
+/// Use `path` instead of searching the settings file, e.g. when given on the command line.
+///
+/// Only the first override takes effect.
+pub fn override_settings_file_path(path: PathBuf) {
+    let _ = SETTINGS_FILE_PATH_OVERRIDE.set(path);
+}
+
+pub fn settings_file_path() -> PathBuf {
+    match SETTINGS_FILE_PATH_OVERRIDE.get() {
+        Some(path) => path.clone(),
+        None => paths::settings_file_path(SETTINGS_FILE_NAME),
+    }
+}
+
+/// Modification time and size of a file, to notice when it changed on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileStamp {
+    modified: Option<SystemTime>,
+    len: u64,
+}
+
+impl FileStamp {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        Some(Self {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+        })
+    }
+}
+
+/// Stamp of the settings file, or `None` if it does not exist.
+pub fn settings_file_stamp() -> Option<FileStamp> {
+    FileStamp::of(&settings_file_path())
+}
+
+/// Upgrades the settings table of one schema version to the next.
+///
+/// The migration at index `n` takes a version `n` table to version `n + 1`.
+type Migration = fn(&mut toml::Table);
+
+const MIGRATIONS: [Migration; SCHEMA_VERSION as usize] = [migrate_v0_to_v1, migrate_v1_to_v2];
+
+/// Files written before the schema was versioned have no `schema_version` key.
+///
+/// Fields added since then are filled with their defaults when deserializing, so the version is
+/// all that is missing.
+fn migrate_v0_to_v1(table: &mut toml::Table) {
+    table.insert("schema_version".to_string(), toml::Value::Integer(1));
+}
+
+/// Durations and start stance moved from the top level into the `Default` profile.
+fn migrate_v1_to_v2(table: &mut toml::Table) {
+    let mut profile = toml::Table::new();
+    for key in [
+        "sit_duration_as_min",
+        "stand_duration_as_min",
+        "start_stance",
+    ] {
+        if let Some(value) = table.remove(key) {
+            profile.insert(key.to_string(), value);
+        }
+    }
+
+    let mut profiles = toml::Table::new();
+    profiles.insert(
+        settings::DEFAULT_PROFILE_NAME.to_string(),
+        toml::Value::Table(profile),
+    );
+    table.insert("profiles".to_string(), toml::Value::Table(profiles));
+    table.insert(
+        "active_profile".to_string(),
+        toml::Value::String(settings::DEFAULT_PROFILE_NAME.to_string()),
+    );
+    table.insert("schema_version".to_string(), toml::Value::Integer(2));
+}
+
+fn schema_version(path: &Path, table: &toml::Table) -> Result<u32, SettingsFileError> {
+    let version = match table.get("schema_version") {
+        None => return Ok(0),
+        Some(toml::Value::Integer(v)) => u32::try_from(*v).ok(),
+        Some(_) => None,
+    };
+
+    match version {
+        Some(v) if v <= SCHEMA_VERSION => Ok(v),
+        _ => Err(SettingsFileError::UnsupportedVersion {
+            path: path.to_path_buf(),
+            version: table.get("schema_version").map(ToString::to_string),
+        }),
+    }
+}
+
+/// Run all migrations needed to bring `table` from `version` to the current schema version.
+fn migrate(mut table: toml::Table, version: u32) -> toml::Table {
+    for migration in &MIGRATIONS[version as usize..] {
+        migration(&mut table);
+    }
+    table
+}
+
+fn parse_error(path: &Path, settings_string: &str, err: toml::de::Error) -> SettingsFileError {
+    SettingsFileError::ParseFile {
+        path: path.to_path_buf(),
+        location: err
+            .span()
+            .map(|span| ParseLocation::from_span(settings_string, span)),
+        source: Box::new(err),
+    }
+}
+
+/// Parse and validate the settings, migrating them from older schema versions.
+///
+/// Returns the settings and the schema version of the file, if it was migrated.
+fn parse_settings(
+    path: &Path,
+    settings_string: &str,
+) -> Result<(settings::Settings, Option<u32>), SettingsFileError> {
+    let table: toml::Table = match toml::from_str(settings_string) {
+        Err(err) => return Err(parse_error(path, settings_string, err)),
+        Ok(t) => t,
+    };
+
+    let version = schema_version(path, &table)?;
+    let (parsed, migrated_from) = if version == SCHEMA_VERSION {
+        // Parse the string itself, so errors point to their location in the file.
+        (toml::from_str(settings_string), None)
+    } else {
+        (migrate(table, version).try_into(), Some(version))
+    };
+    let settings: settings::Settings = match parsed {
+        Err(err) if migrated_from.is_some() => {
+            // Errors of the migrated table have no location, so try to get one from the
+            // original string, where most fields are unchanged.
+            let err = match toml::from_str::<settings::Settings>(settings_string) {
+                Err(located) if located.span().is_some() => located,
+                _ => err,
+            };
+            return Err(parse_error(path, settings_string, err));
+        }
+        Err(err) => return Err(parse_error(path, settings_string, err)),
+        Ok(s) => s,
+    };
+
+    match settings.validate() {
+        Err(errors) => Err(SettingsFileError::Invalid {
+            path: path.to_path_buf(),
+            errors,
+        }),
+        Ok(_) => Ok((settings, migrated_from)),
+    }
+}
+
+/// Copy the values of `source` into `target`, keeping everything else of `target`.
+///
+/// Values which did not change are left untouched, changed ones keep their comments and
+/// whitespace. Keys only present in `target` survive, unless rustnot owns them, see
+/// [`is_owned_key`]. `table_path` is the dotted path of the tables, empty at the top.
+fn merge_into(target: &mut dyn TableLike, source: &dyn TableLike, table_path: &str) {
+    let removed_keys: Vec<String> = target
+        .iter()
+        .map(|(key, _)| key.to_string())
+        .filter(|key| !source.contains_key(key) && is_owned_key(table_path, key))
+        .collect();
+    for key in removed_keys {
+        target.remove(&key);
+    }
+
+    for (key, source_item) in source.iter() {
+        let Some(target_item) = target.get_mut(key) else {
+            target.insert(key, source_item.clone());
+            continue;
+        };
+
+        if let (Some(target_table), Some(source_table)) =
+            (target_item.as_table_like_mut(), source_item.as_table_like())
+        {
+            merge_into(target_table, source_table, &key_path(table_path, key));
+        } else if let (Item::Value(target_value), Item::Value(source_value)) =
+            (&mut *target_item, source_item)
+        {
+            let unchanged = target_value.clone().decorated("", "").to_string()
+                == source_value.clone().decorated("", "").to_string();
+            if !unchanged {
+                let decor = target_value.decor().clone();
+                *target_value = source_value.clone();
+                *target_value.decor_mut() = decor;
+            }
+        } else {
+            *target_item = source_item.clone();
+        }
+    }
+}
+
+/// Whether rustnot writes the key `key` of the table at `table_path` whenever it is set, i.e.
+/// the key is stale if the saved settings lack it.
+fn is_owned_key(table_path: &str, key: &str) -> bool {
+    MAP_TABLES.contains(&table_path) || OPTIONAL_KEYS.contains(&key_path(table_path, key).as_str())
+}
+
+fn key_path(table_path: &str, key: &str) -> String {
+    if table_path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", table_path, key)
+    }
+}
+
+fn migration_backup_path(path: &Path, version: u32) -> PathBuf {
+    paths::with_file_name_suffix(path, &format!(".v{}.bak", version))
+}
+
+/// Path of the copy of the settings file from before the last save.
+fn previous_path(path: &Path) -> PathBuf {
+    paths::with_file_name_suffix(path, ".bak")
+}
+
+/// Atomically replace the file at `path`, keeping its current contents as `.bak`.
+fn replace_file(path: &Path, contents: &str) -> Result<(), SettingsFileError> {
+    let previous_path = previous_path(path);
+    if path.exists()
+        && let Err(err) = fs::copy(path, &previous_path)
+    {
+        return Err(SettingsFileError::Write {
+            path: previous_path,
+            source: Arc::new(err),
+        });
+    }
+
+    match paths::write_atomically(path, contents) {
+        Err(err) => Err(SettingsFileError::Write {
+            path: path.to_path_buf(),
+            source: Arc::new(err),
+        }),
+        Ok(_) => Ok(()),
+    }
 }
 
 impl settings::Settings {
     pub fn load_from_file() -> Result<Self, SettingsFileError> {
-        if !Path::new(SETTINGS_FILE_NAME).exists() {
-            return Err(SettingsFileError::MissingFile);
+        Self::load_from_path(&settings_file_path())
+    }
+
+    /// Read and validate the settings at `path` without changing the file.
+    ///
+    /// Files of an older schema version are only upgraded in memory.
+    pub fn check_file(path: &Path) -> Result<Self, SettingsFileError> {
+        Self::read_from_path(path).map(|(settings, _)| settings)
+    }
+
+    fn read_from_path(path: &Path) -> Result<(Self, Option<u32>), SettingsFileError> {
+        if !path.exists() {
+            return Err(SettingsFileError::MissingFile {
+                path: path.to_path_buf(),
+            });
         }
 
-        let setttings_string = match fs::read_to_string(SETTINGS_FILE_NAME) {
-            Err(_) => return Err(SettingsFileError::Read),
+        let setttings_string = match fs::read_to_string(path) {
+            Err(err) => {
+                return Err(SettingsFileError::Read {
+                    path: path.to_path_buf(),
+                    source: Arc::new(err),
+                });
+            }
             Ok(s) => s,
         };
 
-        match toml::from_str(&setttings_string) {
-            Err(_) => Err(SettingsFileError::ParseFile),
-            Ok(s) => Ok(s),
+        parse_settings(path, &setttings_string)
+    }
+
+    /// Load the settings from `path`.
+    ///
+    /// Files of an older schema version are upgraded in place, after copying the original
+    /// to `<file name>.v<version>.bak` next to it.
+    fn load_from_path(path: &Path) -> Result<Self, SettingsFileError> {
+        let (settings, migrated_from) = Self::read_from_path(path)?;
+        if let Some(version) = migrated_from {
+            let backup_path = migration_backup_path(path, version);
+            if let Err(err) = fs::copy(path, &backup_path) {
+                return Err(SettingsFileError::Write {
+                    path: backup_path,
+                    source: Arc::new(err),
+                });
+            }
+            // Keys may have moved, so the file is written from scratch instead of merged.
+            let settings_string = match toml::to_string_pretty(&settings) {
+                Err(err) => return Err(SettingsFileError::ParseState { source: err }),
+                Ok(s) => s,
+            };
+            replace_file(path, &settings_string)?;
         }
+
+        Ok(settings)
     }
 
     pub fn save_to_file(&self) -> Result<(), SettingsFileError> {
+        self.save_to_path(&settings_file_path())
+    }
+
+    fn save_to_path(&self, path: &Path) -> Result<(), SettingsFileError> {
+        if let Err(errors) = self.validate() {
+            return Err(SettingsFileError::Invalid {
+                path: path.to_path_buf(),
+                errors,
+            });
+        }
+
         let settings_string = match toml::to_string_pretty(&self) {
-            Err(_) => return Err(SettingsFileError::ParseState),
+            Err(err) => return Err(SettingsFileError::ParseState { source: err }),
             Ok(s) => s,
         };
 
-        let mut file = match File::create(SETTINGS_FILE_NAME) {
-            Err(_) => return Err(SettingsFileError::Write),
-            Ok(f) => f,
+        // Only update the values in an existing file, to keep its comments, order and
+        // unknown keys.
+        let existing_document = fs::read_to_string(path)
+            .ok()
+            .and_then(|s| s.parse::<DocumentMut>().ok());
+        let settings_string = match (existing_document, settings_string.parse::<DocumentMut>()) {
+            (Some(mut document), Ok(settings_document)) => {
+                merge_into(document.as_table_mut(), settings_document.as_table(), "");
+                document.to_string()
+            }
+            _ => settings_string,
+        };
+
+        replace_file(path, &settings_string)
+    }
+
+    /// Write only the theme and window geometry to the settings file.
+    ///
+    /// All other values of the file are kept, and so is its `.bak`, which is meant to undo
+    /// explicit saves. Without a settings file, one with the default settings is created.
+    pub fn save_window_state_to_file(&self) -> Result<(), SettingsFileError> {
+        self.save_window_state_to_path(&settings_file_path())
+    }
+
+    fn save_window_state_to_path(&self, path: &Path) -> Result<(), SettingsFileError> {
+        let window_state = settings::Settings {
+            theme: self.theme,
+            window: self.window,
+            ..settings::Settings::default()
+        };
+        let settings_string = match toml::to_string_pretty(&window_state) {
+            Err(err) => return Err(SettingsFileError::ParseState { source: err }),
+            Ok(s) => s,
         };
 
-        match write!(file, "{}", settings_string) {
-            Err(_) => Err(SettingsFileError::Write),
+        let existing_string = match fs::read_to_string(path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(SettingsFileError::Read {
+                    path: path.to_path_buf(),
+                    source: Arc::new(err),
+                });
+            }
+            Ok(s) => Some(s),
+        };
+        let settings_string = match existing_string {
+            None => settings_string,
+            Some(existing_string) => {
+                // A broken file is left for the user to fix, instead of being replaced.
+                if let Err(err) = toml::from_str::<toml::Table>(&existing_string) {
+                    return Err(parse_error(path, &existing_string, err));
+                }
+                let (Ok(mut document), Ok(settings_document)) = (
+                    existing_string.parse::<DocumentMut>(),
+                    settings_string.parse::<DocumentMut>(),
+                ) else {
+                    return Ok(());
+                };
+                let mut window_state_table = toml_edit::Table::new();
+                for key in WINDOW_STATE_KEYS {
+                    if let Some(item) = settings_document.get(key) {
+                        window_state_table.insert(key, item.clone());
+                    }
+                }
+                merge_into(document.as_table_mut(), &window_state_table, "");
+                document.to_string()
+            }
+        };
+
+        match paths::write_atomically(path, &settings_string) {
+            Err(err) => Err(SettingsFileError::Write {
+                path: path.to_path_buf(),
+                source: Arc::new(err),
+            }),
             Ok(_) => Ok(()),
         }
     }
+
+    /// Restore the settings file from before the last save.
+    ///
+    /// The current file becomes the new `.bak`, so restoring twice undoes the restore.
+    pub fn restore_previous_file() -> Result<Self, SettingsFileError> {
+        Self::restore_previous_at(&settings_file_path())
+    }
+
+    fn restore_previous_at(path: &Path) -> Result<Self, SettingsFileError> {
+        let previous_path = previous_path(path);
+        if !previous_path.exists() {
+            return Err(SettingsFileError::MissingFile {
+                path: previous_path,
+            });
+        }
+
+        let previous_string = match fs::read_to_string(&previous_path) {
+            Err(err) => {
+                return Err(SettingsFileError::Read {
+                    path: previous_path,
+                    source: Arc::new(err),
+                });
+            }
+            Ok(s) => s,
+        };
+
+        let (settings, _) = parse_settings(&previous_path, &previous_string)?;
+        replace_file(path, &previous_string)?;
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(
+        settings_string: &str,
+    ) -> Result<(settings::Settings, Option<u32>), SettingsFileError> {
+        parse_settings(Path::new("/config/rustnot_config.toml"), settings_string)
+    }
+
+    fn parse_err(settings_string: &str) -> SettingsFileError {
+        parse(settings_string).unwrap_err()
+    }
+
+    #[test]
+    fn parse_error_points_to_offending_key() {
+        let err = parse_err(
+            "schema_version = 2\n\n[profiles.Default]\nsit_duration_as_min = 40\nstand_duration_as_min = \"twenty\"\n",
+        );
+
+        let SettingsFileError::ParseFile { location, .. } = &err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        let location = location.as_ref().unwrap();
+        assert_eq!(location.line, 5);
+        assert_eq!(location.column, 25);
+        assert_eq!(
+            location.key.as_deref(),
+            Some("profiles.Default.stand_duration_as_min")
+        );
+        assert!(
+            err.to_string()
+                .contains("/config/rustnot_config.toml at line 5")
+        );
+        assert!(
+            err.details()
+                .contains(&("Key", "profiles.Default.stand_duration_as_min".to_string()))
+        );
+    }
+
+    #[test]
+    fn parse_error_key_includes_table() {
+        let err = parse_err(
+            "schema_version = 2\nsnooze_duration_as_min = 5\nconfirm_stance_change = false\n\n[escalation]\nrepeat_after_as_min = -1\n",
+        );
+
+        let SettingsFileError::ParseFile { location, .. } = &err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        let location = location.as_ref().unwrap();
+        assert_eq!(location.line, 6);
+        assert_eq!(
+            location.key.as_deref(),
+            Some("escalation.repeat_after_as_min")
+        );
+    }
+
+    #[test]
+    fn zero_duration_in_file_is_invalid() {
+        let err = parse_err(
+            "schema_version = 2\nactive_profile = \"Default\"\n\n[profiles.Default]\nsit_duration_as_min = 0\n",
+        );
+
+        let SettingsFileError::Invalid { errors, .. } = &err else {
+            panic!("unexpected error: {:?}", err);
+        };
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, settings::SettingsField::SitDuration);
+        assert!(err.details().contains(&(
+            "Invalid",
+            "profiles.\"Default\".sit_duration_as_min: must be at least 1 min".to_string()
+        )));
+    }
+
+    #[test]
+    fn syntax_error_has_location_without_key() {
+        let location = ParseLocation::from_span("a = 1\n[broken\n", 7..8);
+
+        assert_eq!(
+            location,
+            ParseLocation {
+                line: 2,
+                column: 2,
+                key: None,
+            }
+        );
+    }
+
+    /// Format of the first release, before any optional fields existed.
+    const V0_INITIAL: &str = r#"
+sit_duration_as_min = 45
+stand_duration_as_min = 15
+start_stance = "Standing"
+"#;
+
+    /// Last unversioned format, with all fields added before `schema_version`.
+    const V0_UNVERSIONED: &str = r#"
+sit_duration_as_min = 45
+stand_duration_as_min = 15
+start_stance = "Sitting"
+notifier_backend = "Banner"
+snooze_duration_as_min = 10
+confirm_stance_change = true
+daily_standing_goal_as_min = 90
+
+[escalation]
+enabled = true
+repeat_after_as_min = 2
+max_repeat_interval_as_min = 8
+critical_from_step = 1
+raise_window_from_step = 0
+"#;
+
+    /// Format with a single set of durations, before profiles existed.
+    const V1_SINGLE_PROFILE: &str = r#"
+schema_version = 1
+sit_duration_as_min = 50
+stand_duration_as_min = 10
+start_stance = "Standing"
+snooze_duration_as_min = 7
+"#;
+
+    #[test]
+    fn initial_v0_format_migrates_with_defaults() {
+        let (settings, migrated_from) = parse(V0_INITIAL).unwrap();
+
+        assert_eq!(migrated_from, Some(0));
+        assert_eq!(settings.schema_version, SCHEMA_VERSION);
+        assert_eq!(settings.profile().sit_duration_as_min, 45);
+        assert_eq!(settings.profile().stand_duration_as_min, 15);
+        assert_eq!(settings.profile().start_stance, settings::Stance::Standing);
+        assert_eq!(
+            settings.snooze_duration_as_min,
+            settings::Settings::default().snooze_duration_as_min
+        );
+        assert_eq!(settings.escalation, settings::EscalationPolicy::default());
+    }
+
+    #[test]
+    fn unversioned_v0_format_keeps_all_fields() {
+        let (settings, migrated_from) = parse(V0_UNVERSIONED).unwrap();
+
+        assert_eq!(migrated_from, Some(0));
+        assert_eq!(settings.schema_version, SCHEMA_VERSION);
+        assert_eq!(settings.notifier_backend, settings::NotifierBackend::Banner);
+        assert_eq!(settings.snooze_duration_as_min, 10);
+        assert!(settings.confirm_stance_change);
+        assert_eq!(settings.daily_standing_goal_as_min, 90);
+        assert!(settings.escalation.enabled);
+        assert_eq!(settings.escalation.max_repeat_interval_as_min, 8);
+        assert_eq!(settings.escalation.raise_window_from_step, 0);
+    }
+
+    #[test]
+    fn v1_format_moves_durations_into_default_profile() {
+        let (settings, migrated_from) = parse(V1_SINGLE_PROFILE).unwrap();
+
+        assert_eq!(migrated_from, Some(1));
+        assert_eq!(settings.active_profile, settings::DEFAULT_PROFILE_NAME);
+        assert_eq!(
+            settings.profile_names(),
+            vec![settings::DEFAULT_PROFILE_NAME.to_string()]
+        );
+        assert_eq!(settings.profile().sit_duration_as_min, 50);
+        assert_eq!(settings.profile().stand_duration_as_min, 10);
+        assert_eq!(settings.profile().start_stance, settings::Stance::Standing);
+        assert_eq!(settings.snooze_duration_as_min, 7);
+    }
+
+    #[test]
+    fn current_format_is_not_migrated() {
+        let settings_string = toml::to_string_pretty(&settings::Settings::default()).unwrap();
+        assert!(settings_string.starts_with(&format!("schema_version = {}\n", SCHEMA_VERSION)));
+
+        let (_, migrated_from) = parse(&settings_string).unwrap();
+        assert_eq!(migrated_from, None);
+    }
+
+    #[test]
+    fn newer_or_malformed_version_is_rejected() {
+        for version in ["3", "-1", "\"one\""] {
+            let err = parse_err(&format!("schema_version = {}\n{}", version, V0_INITIAL));
+            assert!(
+                matches!(err, SettingsFileError::UnsupportedVersion { .. }),
+                "unexpected error for version {}: {:?}",
+                version,
+                err
+            );
+        }
+    }
+
+    fn temp_settings_path(test_name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("rustnot-{}-{}", test_name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir.join(SETTINGS_FILE_NAME)
+    }
+
+    #[test]
+    fn file_stamp_changes_with_contents() {
+        let path = temp_settings_path("stamp");
+        let dir = path.parent().unwrap();
+
+        let missing = FileStamp::of(&path);
+        fs::write(&path, "a = 1\n").unwrap();
+        let first = FileStamp::of(&path);
+        let unchanged = FileStamp::of(&path);
+        fs::write(&path, "a = 12\n").unwrap();
+        let changed = FileStamp::of(&path);
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(missing, None);
+        assert!(first.is_some());
+        assert_eq!(first, unchanged);
+        assert_ne!(first, changed);
+    }
+
+    #[test]
+    fn migration_rewrites_file_and_keeps_backup() {
+        let path = temp_settings_path("migration");
+        let dir = path.parent().unwrap();
+        fs::write(&path, V0_INITIAL).unwrap();
+
+        let settings = settings::Settings::load_from_path(&path).unwrap();
+        let backup = fs::read_to_string(migration_backup_path(&path, 0)).unwrap();
+        let rewritten = fs::read_to_string(&path).unwrap();
+        let reloaded = parse(&rewritten);
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(backup, V0_INITIAL);
+        assert!(rewritten.contains(&format!("schema_version = {}", SCHEMA_VERSION)));
+        let (reloaded, migrated_from) = reloaded.unwrap();
+        assert_eq!(migrated_from, None);
+        assert_eq!(reloaded.profile(), settings.profile());
+    }
+
+    #[test]
+    fn check_does_not_migrate_file() {
+        let path = temp_settings_path("check");
+        let dir = path.parent().unwrap();
+        fs::write(&path, V0_INITIAL).unwrap();
+
+        let settings = settings::Settings::check_file(&path).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
+        let entries = fs::read_dir(dir).unwrap().count();
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(settings.profile().sit_duration_as_min, 45);
+        assert_eq!(contents, V0_INITIAL);
+        assert_eq!(entries, 1);
+    }
+
+    #[test]
+    fn save_keeps_comments_order_and_unknown_keys() {
+        let path = temp_settings_path("save");
+        let dir = path.parent().unwrap();
+        let original = r#"# Shared team config
+schema_version = 2
+team_note = "ask before changing"
+
+[escalation]
+enabled = true # nag us
+future_option = [1, 2]
+
+# Long sitting phases, we have meetings.
+[profiles.Default]
+sit_duration_as_min = 50 # minutes
+start_stance = "Sitting"
+stand_duration_as_min = 15
+"#;
+        fs::write(&path, original).unwrap();
+
+        let mut settings = settings::Settings::load_from_path(&path).unwrap();
+        settings.profile_mut().sit_duration_as_min = 55;
+        settings.escalation.enabled = false;
+        settings.save_to_path(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let reloaded = settings::Settings::load_from_path(&path);
+        fs::remove_dir_all(dir).unwrap();
+
+        assert!(saved.starts_with("# Shared team config\nschema_version = 2\n"));
+        assert!(saved.contains(
+            "# Long sitting phases, we have meetings.\n[profiles.Default]\nsit_duration_as_min = 55 # minutes\n"
+        ));
+        assert!(saved.contains("start_stance = \"Sitting\"\nstand_duration_as_min = 15\n"));
+        assert!(saved.contains("team_note = \"ask before changing\""));
+        assert!(saved.contains("enabled = false # nag us\nfuture_option = [1, 2]\n"));
+        assert!(saved.contains("snooze_duration_as_min = 5"));
+
+        let reloaded = reloaded.unwrap();
+        assert_eq!(reloaded.profile().sit_duration_as_min, 55);
+        assert!(!reloaded.escalation.enabled);
+    }
+
+    #[test]
+    fn save_removes_deleted_and_renamed_profiles() {
+        let path = temp_settings_path("save-profiles");
+        let dir = path.parent().unwrap();
+        fs::write(
+            &path,
+            "schema_version = 2\n\n[profiles.Default]\nsit_duration_as_min = 50 # long\n",
+        )
+        .unwrap();
+
+        let mut settings = settings::Settings::load_from_path(&path).unwrap();
+        settings.create_profile();
+        settings.save_to_path(&path).unwrap();
+        settings.delete_profile();
+        settings.save_to_path(&path).unwrap();
+        let after_delete = settings::Settings::load_from_path(&path).unwrap();
+        settings.rename_profile("Office").unwrap();
+        settings.save_to_path(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let after_rename = settings::Settings::load_from_path(&path).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(after_delete.profile_names(), vec!["Default"]);
+        assert_eq!(after_delete.profile().sit_duration_as_min, 50);
+        assert_eq!(after_rename.profile_names(), vec!["Office"]);
+        assert_eq!(after_rename.active_profile, "Office");
+        assert_eq!(after_rename.profile().sit_duration_as_min, 50);
+        assert!(!saved.contains("[profiles.Default]"));
+    }
+
+    #[test]
+    fn save_removes_unset_optional_keys() {
+        let path = temp_settings_path("save-unset");
+        let dir = path.parent().unwrap();
+        fs::write(
+            &path,
+            "schema_version = 2\n\n[window]\nwidth = 480\nx = 10 # left\ny = 20\nmonitor = 1\n",
+        )
+        .unwrap();
+
+        let mut settings = settings::Settings::load_from_path(&path).unwrap();
+        settings.window.x = None;
+        settings.window.y = None;
+        settings.save_to_path(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let reloaded = settings::Settings::load_from_path(&path).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+
+        assert!(!saved.contains("\nx = "));
+        assert!(!saved.contains("\ny = "));
+        assert!(saved.contains("monitor = 1\n"));
+        assert_eq!(reloaded.window.position(), None);
+    }
+
+    #[test]
+    fn theme_and_window_geometry_round_trip() {
+        let mut settings = settings::Settings {
+            theme: settings::ThemeChoice::Dark,
+            ..settings::Settings::default()
+        };
+        let without_position = toml::to_string_pretty(&settings).unwrap();
+        settings.window = settings::WindowGeometry {
+            width: 600,
+            height: 500,
+            x: Some(-20),
+            y: Some(40),
+            always_on_top: true,
+        };
+
+        let (reloaded, _) = parse(&toml::to_string_pretty(&settings).unwrap()).unwrap();
+
+        assert!(!without_position.contains("x ="));
+        assert_eq!(reloaded.theme, settings::ThemeChoice::Dark);
+        assert_eq!(reloaded.window, settings.window);
+        assert_eq!(reloaded.window.position(), Some((-20, 40)));
+    }
+
+    #[test]
+    fn window_state_save_changes_only_theme_and_window() {
+        let path = temp_settings_path("window-state");
+        let dir = path.parent().unwrap();
+        let original = r#"# Shared team config
+schema_version = 2
+theme = "Light" # for the projector
+
+[profiles.Default]
+sit_duration_as_min = 50
+"#;
+        fs::write(&path, original).unwrap();
+
+        let mut settings = settings::Settings::load_from_path(&path).unwrap();
+        settings.profile_mut().sit_duration_as_min = 20;
+        settings.theme = settings::ThemeChoice::Dark;
+        settings.window.width = 640;
+        settings.save_window_state_to_path(&path).unwrap();
+        let saved = fs::read_to_string(&path).unwrap();
+        let reloaded = settings::Settings::load_from_path(&path).unwrap();
+        let backup_exists = previous_path(&path).exists();
+        fs::remove_dir_all(dir).unwrap();
+
+        assert!(saved.starts_with("# Shared team config\nschema_version = 2\n"));
+        assert!(saved.contains("theme = \"Dark\" # for the projector\n"));
+        assert_eq!(reloaded.theme, settings::ThemeChoice::Dark);
+        assert_eq!(reloaded.window.width, 640);
+        assert_eq!(reloaded.profile().sit_duration_as_min, 50);
+        assert!(!backup_exists);
+    }
+
+    #[test]
+    fn window_state_save_creates_missing_file_but_keeps_broken_one() {
+        let path = temp_settings_path("window-state-missing");
+        let dir = path.parent().unwrap();
+        let mut settings = settings::Settings::default();
+        settings.profile_mut().sit_duration_as_min = 20;
+        settings.theme = settings::ThemeChoice::Light;
+
+        settings.save_window_state_to_path(&path).unwrap();
+        let created = settings::Settings::load_from_path(&path).unwrap();
+        fs::write(&path, "theme = ").unwrap();
+        let broken = settings.save_window_state_to_path(&path);
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(dir).unwrap();
+
+        assert_eq!(created.theme, settings::ThemeChoice::Light);
+        assert_eq!(created.profile(), settings::Settings::default().profile());
+        assert!(matches!(broken, Err(SettingsFileError::ParseFile { .. })));
+        assert_eq!(contents, "theme = ");
+    }
+
+    #[test]
+    fn save_keeps_previous_file_which_can_be_restored() {
+        let path = temp_settings_path("restore");
+        let dir = path.parent().unwrap();
+        let mut first = settings::Settings::default();
+        first.profile_mut().sit_duration_as_min = 30;
+        let mut second = settings::Settings::default();
+        second.profile_mut().sit_duration_as_min = 60;
+
+        let missing = settings::Settings::restore_previous_at(&path);
+        first.save_to_path(&path).unwrap();
+        second.save_to_path(&path).unwrap();
+        let restored = settings::Settings::restore_previous_at(&path).unwrap();
+        let loaded = settings::Settings::load_from_path(&path).unwrap();
+        let restored_again = settings::Settings::restore_previous_at(&path).unwrap();
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        fs::remove_dir_all(dir).unwrap();
+
+        assert!(matches!(
+            missing,
+            Err(SettingsFileError::MissingFile { .. })
+        ));
+        assert_eq!(restored.profile().sit_duration_as_min, 30);
+        assert_eq!(loaded.profile().sit_duration_as_min, 30);
+        assert_eq!(restored_again.profile().sit_duration_as_min, 60);
+        assert_eq!(
+            files,
+            vec!["rustnot_config.toml", "rustnot_config.toml.bak"]
+        );
+    }
 }
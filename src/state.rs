@@ -0,0 +1,231 @@
+//! State of the running cycle across restarts, see the _State file_ section of the README.
+
+use crate::paths;
+use crate::settings::Stance;
+use crate::settings_file::settings_file_path;
+use crate::timer::SavedCycle;
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use std::{fs, io};
+
+use chrono::{DateTime, Local};
+use thiserror::Error;
+
+pub const STATE_FILE_NAME: &str = "rustnot_state.toml";
+
+pub fn state_file_path() -> PathBuf {
+    settings_file_path().with_file_name(STATE_FILE_NAME)
+}
+
+#[derive(Error, Debug, Clone)]
+pub enum StateFileError {
+    #[error("Failed to read the state file {}: {source}", path.display())]
+    Read {
+        path: PathBuf,
+        source: Arc<io::Error>,
+    },
+    #[error("Failed to write the state file {}: {source}", path.display())]
+    Write {
+        path: PathBuf,
+        source: Arc<io::Error>,
+    },
+    #[error("Failed to parse the state file {}: {}", path.display(), source.message())]
+    Parse {
+        path: PathBuf,
+        source: Box<toml::de::Error>,
+    },
+    #[error("Failed to serialize the cycle state: {source}")]
+    Serialize { source: toml::ser::Error },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct CycleState {
+    pub stance: Stance,
+    #[serde(default)]
+    pub profile: Option<String>,
+    pub started_at: DateTime<Local>,
+    pub duration_as_sec: u64,
+    pub elapsed_as_sec: u64,
+    pub paused: bool,
+    pub awaiting_confirmation: bool,
+    pub acknowledged: bool,
+    pub snoozed: bool,
+    pub reminders: u32,
+    pub saved_at: DateTime<Local>,
+}
+
+impl From<&SavedCycle> for CycleState {
+    fn from(cycle: &SavedCycle) -> Self {
+        Self {
+            stance: cycle.stance,
+            profile: cycle.profile.clone(),
+            started_at: DateTime::<Local>::from(cycle.started_at),
+            duration_as_sec: cycle.duration.as_secs(),
+            elapsed_as_sec: cycle.elapsed.as_secs(),
+            paused: cycle.paused,
+            awaiting_confirmation: cycle.awaiting_confirmation,
+            acknowledged: cycle.acknowledged,
+            snoozed: cycle.snoozed,
+            reminders: cycle.reminders,
+            saved_at: DateTime::<Local>::from(cycle.saved_at),
+        }
+    }
+}
+
+impl From<&CycleState> for SavedCycle {
+    fn from(state: &CycleState) -> Self {
+        Self {
+            stance: state.stance,
+            profile: state.profile.clone(),
+            started_at: state.started_at.into(),
+            duration: Duration::from_secs(state.duration_as_sec),
+            elapsed: Duration::from_secs(state.elapsed_as_sec),
+            paused: state.paused,
+            awaiting_confirmation: state.awaiting_confirmation,
+            acknowledged: state.acknowledged,
+            snoozed: state.snoozed,
+            reminders: state.reminders,
+            saved_at: state.saved_at.into(),
+        }
+    }
+}
+
+impl CycleState {
+    /// Replace the state file with `cycle`, or remove it if no cycle is running.
+    pub fn save_to_file(cycle: Option<&SavedCycle>) -> Result<(), StateFileError> {
+        Self::save_to_path(&state_file_path(), cycle)
+    }
+
+    fn save_to_path(path: &Path, cycle: Option<&SavedCycle>) -> Result<(), StateFileError> {
+        let Some(cycle) = cycle else {
+            return match fs::remove_file(path) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => Err(StateFileError::Write {
+                    path: path.to_path_buf(),
+                    source: Arc::new(err),
+                }),
+                _ => Ok(()),
+            };
+        };
+
+        let state_string = match toml::to_string(&CycleState::from(cycle)) {
+            Err(err) => return Err(StateFileError::Serialize { source: err }),
+            Ok(s) => s,
+        };
+        match paths::write_atomically(path, &state_string) {
+            Err(err) => Err(StateFileError::Write {
+                path: path.to_path_buf(),
+                source: Arc::new(err),
+            }),
+            Ok(_) => Ok(()),
+        }
+    }
+
+    /// The cycle of the state file, if there is one.
+    pub fn load_from_file() -> Result<Option<SavedCycle>, StateFileError> {
+        Self::load_from_path(&state_file_path())
+    }
+
+    fn load_from_path(path: &Path) -> Result<Option<SavedCycle>, StateFileError> {
+        if !path.exists() {
+            return Ok(None);
+        }
+
+        let state_string = match fs::read_to_string(path) {
+            Err(err) => {
+                return Err(StateFileError::Read {
+                    path: path.to_path_buf(),
+                    source: Arc::new(err),
+                });
+            }
+            Ok(s) => s,
+        };
+        match toml::from_str::<CycleState>(&state_string) {
+            Err(err) => Err(StateFileError::Parse {
+                path: path.to_path_buf(),
+                source: Box::new(err),
+            }),
+            Ok(state) => Ok(Some(SavedCycle::from(&state))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::time::SystemTime;
+
+    #[test]
+    fn state_round_trips_through_toml() {
+        let saved_at = SystemTime::UNIX_EPOCH + Duration::from_secs(1_790_000_000);
+        let cycle = SavedCycle {
+            stance: Stance::Standing,
+            profile: Some("Office".to_string()),
+            started_at: saved_at - Duration::from_mins(5),
+            duration: Duration::from_mins(15),
+            elapsed: Duration::from_mins(5),
+            paused: true,
+            awaiting_confirmation: false,
+            acknowledged: true,
+            snoozed: false,
+            reminders: 1,
+            saved_at,
+        };
+
+        let state_string = toml::to_string(&CycleState::from(&cycle)).unwrap();
+        let state: CycleState = toml::from_str(&state_string).unwrap();
+
+        assert!(state_string.contains("stance = \"Standing\""));
+        assert!(state_string.contains("elapsed_as_sec = 300"));
+        assert_eq!(SavedCycle::from(&state), cycle);
+    }
+
+    #[test]
+    fn file_errors_name_the_state_file() {
+        let dir = std::env::temp_dir().join(format!("rustnot-state-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(STATE_FILE_NAME);
+
+        let missing = CycleState::load_from_path(&path);
+        CycleState::save_to_path(&path, None).unwrap();
+        fs::write(&path, "stance = ").unwrap();
+        let broken = CycleState::load_from_path(&path);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(missing.unwrap(), None);
+        let Err(err @ StateFileError::Parse { .. }) = broken else {
+            panic!("unexpected result: {:?}", broken);
+        };
+        assert!(err.to_string().contains(&path.display().to_string()));
+    }
+
+    #[test]
+    fn documented_example_parses() {
+        let state: CycleState = toml::from_str(
+            r#"
+stance = "Standing"
+profile = "Office"
+started_at = "2026-10-18T09:00:00+02:00"
+duration_as_sec = 900
+elapsed_as_sec = 300
+paused = false
+awaiting_confirmation = false
+acknowledged = true
+snoozed = false
+reminders = 0
+saved_at = "2026-10-18T09:05:00+02:00"
+"#,
+        )
+        .unwrap();
+
+        let cycle = SavedCycle::from(&state);
+        assert!(cycle.is_counting());
+        assert_eq!(cycle.profile.as_deref(), Some("Office"));
+        assert_eq!(
+            cycle.saved_at.duration_since(cycle.started_at).unwrap(),
+            Duration::from_mins(5)
+        );
+    }
+}
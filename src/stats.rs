@@ -0,0 +1,266 @@
+use crate::history::CycleRecord;
+use crate::settings::Stance;
+use crate::timer::CycleEnd;
+
+use chrono::{Datelike, Days, NaiveDate};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct StanceTotals {
+    pub standing_as_sec: u64,
+    pub sitting_as_sec: u64,
+    pub completed_cycles: u32,
+    pub skipped_cycles: u32,
+    /// Reminders sent for stance changes which were not reacted to.
+    pub reminders: u32,
+    /// Stance changes with at least one reminder.
+    pub ignored_stance_changes: u32,
+}
+
+impl StanceTotals {
+    pub fn standing_as_min(&self) -> u64 {
+        self.standing_as_sec / 60
+    }
+
+    pub fn sitting_as_min(&self) -> u64 {
+        self.sitting_as_sec / 60
+    }
+
+    fn add(&mut self, record: &CycleRecord) {
+        match record.stance {
+            Stance::Standing => self.standing_as_sec += record.actual_duration_as_sec,
+            Stance::Sitting => self.sitting_as_sec += record.actual_duration_as_sec,
+        }
+        match record.end {
+            CycleEnd::Natural => self.completed_cycles += 1,
+            CycleEnd::Skip => self.skipped_cycles += 1,
+            CycleEnd::Stop | CycleEnd::Snooze | CycleEnd::Suspend => (),
+        }
+        self.reminders += record.reminders;
+        if record.reminders > 0 {
+            self.ignored_stance_changes += 1;
+        }
+    }
+}
+
+/// Totals of the cycle history for today and the current week.
+///
+/// Cycles count for the day they started on.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Statistics {
+    /// Day the statistics were computed for.
+    pub date: NaiveDate,
+    pub today: StanceTotals,
+    pub week: StanceTotals,
+    /// Totals for each day of the current week, starting on Monday.
+    pub week_days: [StanceTotals; 7],
+}
+
+impl Statistics {
+    pub fn from_records(records: &[CycleRecord], today: NaiveDate) -> Self {
+        let week_start = today - Days::new(today.weekday().num_days_from_monday().into());
+        let mut statistics = Self {
+            date: today,
+            ..Self::default()
+        };
+
+        for record in records {
+            let date = record.timestamp.date_naive();
+            if date < week_start || date > today {
+                continue;
+            }
+            statistics.week.add(record);
+            statistics.week_days[date.weekday().num_days_from_monday() as usize].add(record);
+            if date == today {
+                statistics.today.add(record);
+            }
+        }
+
+        statistics
+    }
+}
+
+/// Progress towards the daily standing goal.
+///
+/// Finished cycles are added when they end; the time of a running standing cycle is passed in
+/// when checking the progress, so it counts while the cycle runs.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct StandingGoal {
+    date: NaiveDate,
+    standing_as_sec: u64,
+    reached: bool,
+}
+
+impl StandingGoal {
+    pub fn from_records(records: &[CycleRecord], today: NaiveDate, goal_as_min: u64) -> Self {
+        let mut standing_goal = Self {
+            date: today,
+            standing_as_sec: Statistics::from_records(records, today)
+                .today
+                .standing_as_sec,
+            reached: false,
+        };
+        standing_goal.check(goal_as_min, 0, today);
+        standing_goal
+    }
+
+    pub fn add(&mut self, record: &CycleRecord) {
+        if record.stance == Stance::Standing && record.timestamp.date_naive() == self.date {
+            self.standing_as_sec += record.actual_duration_as_sec;
+        }
+    }
+
+    pub fn standing_as_sec(&self, running_as_sec: u64) -> u64 {
+        self.standing_as_sec + running_as_sec
+    }
+
+    /// Start counting from zero when the day changed.
+    fn roll_over(&mut self, today: NaiveDate) {
+        if self.date != today {
+            *self = Self {
+                date: today,
+                ..Self::default()
+            };
+        }
+    }
+
+    /// Returns `true` only for the check which first sees the goal reached on a day.
+    ///
+    /// A goal of `0` minutes disables it.
+    pub fn check(&mut self, goal_as_min: u64, running_as_sec: u64, today: NaiveDate) -> bool {
+        self.roll_over(today);
+        let reached = goal_as_min > 0 && self.standing_as_sec(running_as_sec) >= goal_as_min * 60;
+        let newly_reached = reached && !self.reached;
+        self.reached = reached;
+        newly_reached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::{DateTime, Local, NaiveTime, TimeZone};
+
+    fn record(date: NaiveDate, stance: Stance, minutes: u64, end: CycleEnd) -> CycleRecord {
+        record_with_reminders(date, stance, minutes, end, 0)
+    }
+
+    fn record_with_reminders(
+        date: NaiveDate,
+        stance: Stance,
+        minutes: u64,
+        end: CycleEnd,
+        reminders: u32,
+    ) -> CycleRecord {
+        CycleRecord {
+            timestamp: at(date),
+            stance,
+            planned_duration_as_sec: minutes * 60,
+            actual_duration_as_sec: minutes * 60,
+            end,
+            acknowledged: false,
+            reminders,
+            profile: None,
+        }
+    }
+
+    fn at(date: NaiveDate) -> DateTime<Local> {
+        Local
+            .from_local_datetime(&date.and_time(NaiveTime::from_hms_opt(10, 0, 0).unwrap()))
+            .unwrap()
+    }
+
+    fn date(day: u32) -> NaiveDate {
+        // 2026-10-12 is a Monday.
+        NaiveDate::from_ymd_opt(2026, 10, day).unwrap()
+    }
+
+    #[test]
+    fn totals_split_by_today_and_week() {
+        let today = date(15);
+        let records = vec![
+            record(date(11), Stance::Standing, 30, CycleEnd::Natural),
+            record(date(12), Stance::Standing, 15, CycleEnd::Natural),
+            record(date(12), Stance::Sitting, 45, CycleEnd::Natural),
+            record(date(15), Stance::Sitting, 40, CycleEnd::Natural),
+            record_with_reminders(date(15), Stance::Standing, 5, CycleEnd::Skip, 3),
+            record(date(15), Stance::Sitting, 12, CycleEnd::Stop),
+            record(date(16), Stance::Standing, 60, CycleEnd::Natural),
+        ];
+
+        let statistics = Statistics::from_records(&records, today);
+
+        assert_eq!(
+            statistics.today,
+            StanceTotals {
+                standing_as_sec: 5 * 60,
+                sitting_as_sec: 52 * 60,
+                completed_cycles: 1,
+                skipped_cycles: 1,
+                reminders: 3,
+                ignored_stance_changes: 1,
+            }
+        );
+        assert_eq!(statistics.date, today);
+        assert_eq!(statistics.week.standing_as_min(), 20);
+        assert_eq!(statistics.week.sitting_as_min(), 97);
+        assert_eq!(statistics.week.completed_cycles, 3);
+        assert_eq!(statistics.week.skipped_cycles, 1);
+        assert_eq!(statistics.week_days[0].standing_as_min(), 15);
+        assert_eq!(statistics.week_days[3], statistics.today);
+        assert_eq!(statistics.week_days[6], StanceTotals::default());
+    }
+
+    #[test]
+    fn week_starts_on_monday() {
+        let records = vec![
+            record(date(18), Stance::Standing, 10, CycleEnd::Natural),
+            record(date(19), Stance::Standing, 20, CycleEnd::Natural),
+        ];
+
+        let sunday = Statistics::from_records(&records, date(18));
+        assert_eq!(sunday.week.standing_as_min(), 10);
+        assert_eq!(sunday.week_days[6].standing_as_min(), 10);
+
+        let monday = Statistics::from_records(&records, date(19));
+        assert_eq!(monday.week.standing_as_min(), 20);
+        assert_eq!(monday.today.standing_as_min(), 20);
+    }
+
+    #[test]
+    fn standing_goal_counts_only_standing_time_of_today() {
+        let today = date(15);
+        let records = vec![
+            record(date(14), Stance::Standing, 60, CycleEnd::Natural),
+            record(date(15), Stance::Standing, 20, CycleEnd::Natural),
+            record(date(15), Stance::Sitting, 40, CycleEnd::Natural),
+        ];
+
+        let mut standing_goal = StandingGoal::from_records(&records, today, 60);
+        assert_eq!(standing_goal.standing_as_sec(0), 20 * 60);
+
+        standing_goal.add(&record(date(15), Stance::Sitting, 30, CycleEnd::Natural));
+        standing_goal.add(&record(date(15), Stance::Standing, 25, CycleEnd::Skip));
+        assert_eq!(standing_goal.standing_as_sec(0), 45 * 60);
+        assert!(!standing_goal.check(60, 14 * 60, today));
+        assert!(standing_goal.check(60, 15 * 60, today));
+        assert!(!standing_goal.check(60, 16 * 60, today));
+    }
+
+    #[test]
+    fn standing_goal_resets_on_new_day() {
+        let records = vec![record(date(15), Stance::Standing, 90, CycleEnd::Natural)];
+        let mut standing_goal = StandingGoal::from_records(&records, date(15), 60);
+
+        assert!(!standing_goal.check(60, 0, date(15)));
+        assert!(!standing_goal.check(60, 0, date(16)));
+        assert_eq!(standing_goal.standing_as_sec(0), 0);
+        assert!(standing_goal.check(60, 60 * 60, date(16)));
+    }
+
+    #[test]
+    fn zero_standing_goal_is_never_reached() {
+        let mut standing_goal = StandingGoal::from_records(&[], date(15), 0);
+        assert!(!standing_goal.check(0, 10 * 60 * 60, date(15)));
+    }
+}
@@ -6,14 +6,28 @@
 pub const HORIZONTAL_RULE_HEIGHT: u32 = 2;
 
 pub const BUTTON_PADDING: [u16; 2] = [8, 10];
+pub const BANNER_PADDING: u16 = 8;
+pub const ERROR_PANEL_LABEL_WIDTH: u32 = 65;
 
 pub const COL_SPACING: u32 = 5;
 
+pub const SETTINGS_FIELDS_HEIGHT: u32 = 180;
+pub const SCROLLBAR_GAP: f32 = 15.0;
+
 pub const ROW_PADDING: u16 = 15;
 pub const ROW_SPACING: u32 = 10;
 
 pub const TEXT_SIZE_HEADING: u32 = 45;
 pub const TEXT_SIZE_NORMAL: u32 = 20;
+pub const TEXT_SIZE_SMALL: u32 = 14;
+
+pub const PROGRESS_BAR_LENGTH: u32 = 90;
+pub const PROGRESS_BAR_GIRTH: u32 = 10;
+
+pub const PROFILE_PICK_LIST_WIDTH: u32 = 120;
+
+pub const STATISTICS_COLUMN_WIDTH: u32 = 70;
+pub const STATISTICS_CHART_HEIGHT: u32 = 140;
 
 pub fn tooltip_style(theme: &iced::Theme) -> container::Style {
     container::Style {
@@ -25,3 +39,22 @@ pub fn tooltip_style(theme: &iced::Theme) -> container::Style {
         ..container::rounded_box(theme)
     }
 }
+
+pub fn banner_style(theme: &iced::Theme, is_error: bool) -> container::Style {
+    let palette = theme.extended_palette();
+    let pair = if is_error {
+        palette.danger.weak
+    } else {
+        palette.primary.weak
+    };
+    container::Style {
+        background: Some(pair.color.into()),
+        text_color: Some(pair.text),
+        border: iced::Border {
+            color: pair.text,
+            width: 1.0,
+            radius: iced::border::radius(3),
+        },
+        ..container::Style::default()
+    }
+}
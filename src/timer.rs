@@ -0,0 +1,1491 @@
+use crate::escalation::EscalationStep;
+use crate::settings::{Settings, Stance, WakePolicy};
+
+use std::time::SystemTime;
+
+use iced::time::{Duration, Instant};
+
+pub trait Clock {
+    /// Monotonic time, used for all countdowns.
+    fn now(&self) -> Instant;
+    /// Wall-clock time, only used to timestamp cycles.
+    fn system_time(&self) -> SystemTime;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn system_time(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// Identifies a cycle, to tell whether an answer to a notification still refers to it.
+pub type CycleId = u64;
+
+#[derive(Debug, Clone)]
+pub struct TimerCycleInfo {
+    pub id: CycleId,
+    pub start_time: Instant,
+    /// Time counted for the cycle beyond the monotonic clock, e.g. the time it ran in an
+    /// earlier run of the app or while the system was suspended.
+    pub elapsed_offset: Duration,
+    pub started_at: SystemTime,
+    pub duration: Duration,
+    pub stance: Stance,
+    /// Name of the settings profile active when the cycle started.
+    pub profile: String,
+    pub paused_at: Option<Instant>,
+    pub acknowledged: bool,
+    /// Set while the countdown waits for the user to confirm the stance change.
+    pub awaiting_confirmation_since: Option<Instant>,
+    /// Set for cycles started by the timer itself, until the user interacts with it.
+    pub escalating: bool,
+    /// Set for cycles started by the timer changing the stance, as opposed to the user.
+    pub from_stance_change: bool,
+    /// Set for cycles started by snoozing, which last for the snooze duration.
+    pub snoozed: bool,
+    pub reminders_sent: u32,
+    pub last_notified_at: Instant,
+}
+
+impl TimerCycleInfo {
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.duration.saturating_sub(self.active_duration(now))
+    }
+
+    /// Time the countdown actually ran, without pauses and time awaiting confirmation.
+    pub fn active_duration(&self, now: Instant) -> Duration {
+        let frozen_at = self.paused_at.or(self.awaiting_confirmation_since);
+        self.elapsed_offset + (frozen_at.unwrap_or(now) - self.start_time)
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused_at.is_some()
+    }
+
+    pub fn is_awaiting_confirmation(&self) -> bool {
+        self.awaiting_confirmation_since.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerEvent {
+    Started {
+        stance: Stance,
+        duration: Duration,
+    },
+    StanceChanged {
+        stance: Stance,
+        duration: Duration,
+    },
+    Stopped,
+    Paused,
+    Resumed,
+    Reset,
+    Snoozed {
+        stance: Stance,
+        duration: Duration,
+    },
+    Acknowledged,
+    Restored {
+        stance: Stance,
+        remaining: Duration,
+    },
+    /// The system was suspended for `slept`, the wake policy was applied.
+    Woke {
+        slept: Duration,
+        policy: WakePolicy,
+    },
+    Reminder {
+        stance: Stance,
+        step: EscalationStep,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CycleEnd {
+    /// The countdown ran out.
+    Natural,
+    Skip,
+    Stop,
+    Snooze,
+    /// The system was suspended and the timer restarted on wake.
+    Suspend,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FinishedCycle {
+    pub stance: Stance,
+    pub profile: String,
+    pub started_at: SystemTime,
+    pub planned_duration: Duration,
+    pub actual_duration: Duration,
+    pub end: CycleEnd,
+    pub acknowledged: bool,
+    pub reminders: u32,
+}
+
+/// State of the current cycle, to continue it after the app was restarted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedCycle {
+    pub stance: Stance,
+    /// Missing in cycles saved before the profile was stored with them.
+    pub profile: Option<String>,
+    pub started_at: SystemTime,
+    pub duration: Duration,
+    /// Time the countdown ran until `saved_at`.
+    pub elapsed: Duration,
+    pub paused: bool,
+    pub awaiting_confirmation: bool,
+    pub acknowledged: bool,
+    pub snoozed: bool,
+    pub reminders: u32,
+    pub saved_at: SystemTime,
+}
+
+impl SavedCycle {
+    /// Whether the countdown was running when it was saved, i.e. time passed since then counts.
+    pub fn is_counting(&self) -> bool {
+        !self.paused && !self.awaiting_confirmation
+    }
+}
+
+/// How to continue a saved cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RestoreMode {
+    /// The time passed since saving counts as spent in the cycle, unless it was paused or
+    /// awaited confirmation.
+    Continue,
+    /// The cycle starts over with its full duration, like after a reset.
+    Fresh,
+}
+
+#[derive(Debug, Default)]
+pub struct Timer<C: Clock = SystemClock> {
+    clock: C,
+    current_cycle: Option<TimerCycleInfo>,
+    finished_cycles: Vec<FinishedCycle>,
+    /// Monotonic and wall-clock time of the last tick, to notice suspends.
+    last_tick: Option<(Instant, SystemTime)>,
+    next_cycle_id: CycleId,
+}
+
+/// Minimum gap between the wall clock and the monotonic clock across one tick, which counts
+/// as suspend. Smaller gaps are clock adjustments, e.g. by NTP.
+pub const SUSPEND_THRESHOLD: Duration = Duration::from_secs(30);
+
+impl<C: Clock> Timer<C> {
+    pub fn new(clock: C) -> Self {
+        Self {
+            clock,
+            current_cycle: None,
+            finished_cycles: Vec::new(),
+            last_tick: None,
+            next_cycle_id: 0,
+        }
+    }
+
+    /// Cycles ended since the last call.
+    pub fn take_finished_cycles(&mut self) -> Vec<FinishedCycle> {
+        std::mem::take(&mut self.finished_cycles)
+    }
+
+    pub fn current_cycle(&self) -> Option<&TimerCycleInfo> {
+        self.current_cycle.as_ref()
+    }
+
+    pub fn is_current_cycle(&self, cycle_id: CycleId) -> bool {
+        self.current_cycle
+            .as_ref()
+            .is_some_and(|c| c.id == cycle_id)
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.current_cycle.is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.current_cycle.as_ref().is_some_and(|c| c.is_paused())
+    }
+
+    pub fn is_awaiting_confirmation(&self) -> bool {
+        self.current_cycle
+            .as_ref()
+            .is_some_and(|c| c.is_awaiting_confirmation())
+    }
+
+    pub fn remaining(&self) -> Option<Duration> {
+        self.current_cycle
+            .as_ref()
+            .map(|cycle_info| cycle_info.remaining(self.clock.now()))
+    }
+
+    /// Time since the last notification about the current cycle.
+    pub fn since_last_notification(&self) -> Option<Duration> {
+        self.current_cycle
+            .as_ref()
+            .map(|cycle_info| self.clock.now() - cycle_info.last_notified_at)
+    }
+
+    pub fn active_duration(&self) -> Option<Duration> {
+        self.current_cycle
+            .as_ref()
+            .map(|cycle_info| cycle_info.active_duration(self.clock.now()))
+    }
+
+    pub fn save_cycle(&self) -> Option<SavedCycle> {
+        let cycle_info = self.current_cycle.as_ref()?;
+        Some(SavedCycle {
+            stance: cycle_info.stance,
+            profile: Some(cycle_info.profile.clone()),
+            started_at: cycle_info.started_at,
+            duration: cycle_info.duration,
+            elapsed: cycle_info.active_duration(self.clock.now()),
+            paused: cycle_info.is_paused(),
+            awaiting_confirmation: cycle_info.is_awaiting_confirmation(),
+            acknowledged: cycle_info.acknowledged,
+            snoozed: cycle_info.snoozed,
+            reminders: cycle_info.reminders_sent,
+            saved_at: self.clock.system_time(),
+        })
+    }
+
+    /// Make a cycle saved by an earlier run of the app the current one.
+    ///
+    /// Does nothing if a cycle is running already. If a continued cycle ran out while the app
+    /// was closed, it counts with its planned duration and the next cycle starts right away.
+    pub fn restore_cycle(
+        &mut self,
+        settings: &Settings,
+        saved: &SavedCycle,
+        mode: RestoreMode,
+    ) -> Option<TimerEvent> {
+        if self.current_cycle.is_some() {
+            return None;
+        }
+        let now = self.clock.now();
+        self.last_tick = None;
+        let elapsed_offset = match mode {
+            RestoreMode::Fresh => Duration::ZERO,
+            RestoreMode::Continue if saved.is_counting() => {
+                // The wall clock may have been set back in between.
+                let closed_for = self
+                    .clock
+                    .system_time()
+                    .duration_since(saved.saved_at)
+                    .unwrap_or_default();
+                // Time after the cycle ran out was not spent in it.
+                (saved.elapsed + closed_for).min(saved.duration.max(saved.elapsed))
+            }
+            RestoreMode::Continue => saved.elapsed,
+        };
+
+        let cycle_info = TimerCycleInfo {
+            id: self.next_cycle_id(),
+            start_time: now,
+            elapsed_offset,
+            started_at: saved.started_at,
+            duration: saved.duration,
+            stance: saved.stance,
+            profile: saved
+                .profile
+                .clone()
+                .unwrap_or_else(|| settings.active_profile.clone()),
+            paused_at: saved.paused.then_some(now),
+            acknowledged: saved.acknowledged,
+            awaiting_confirmation_since: saved.awaiting_confirmation.then_some(now),
+            escalating: saved.awaiting_confirmation,
+            // Only a stance change awaiting confirmation is notified about again.
+            from_stance_change: saved.awaiting_confirmation,
+            snoozed: saved.snoozed,
+            reminders_sent: saved.reminders,
+            last_notified_at: now,
+        };
+        if mode == RestoreMode::Continue
+            && saved.is_counting()
+            && cycle_info.remaining(now).is_zero()
+        {
+            return Some(self.end_ran_out_cycle(settings, &cycle_info, now));
+        }
+        let event = TimerEvent::Restored {
+            stance: cycle_info.stance,
+            remaining: cycle_info.remaining(now),
+        };
+        self.current_cycle = Some(cycle_info);
+        Some(event)
+    }
+
+    pub fn start(&mut self, settings: &Settings) -> Option<TimerEvent> {
+        if self.current_cycle.is_some() {
+            return None;
+        }
+        let cycle_info = self.new_cycle(settings, settings.profile().start_stance);
+        let event = TimerEvent::Started {
+            stance: cycle_info.stance,
+            duration: cycle_info.duration,
+        };
+        self.current_cycle = Some(cycle_info);
+        self.last_tick = None;
+        Some(event)
+    }
+
+    pub fn stop(&mut self) -> Option<TimerEvent> {
+        let cycle_info = self.current_cycle.take()?;
+        self.finish_cycle(&cycle_info, CycleEnd::Stop);
+        Some(TimerEvent::Stopped)
+    }
+
+    pub fn pause(&mut self) -> Option<TimerEvent> {
+        let now = self.clock.now();
+        match &mut self.current_cycle {
+            Some(cycle_info)
+                if !cycle_info.is_paused() && !cycle_info.is_awaiting_confirmation() =>
+            {
+                cycle_info.paused_at = Some(now);
+                cycle_info.escalating = false;
+                Some(TimerEvent::Paused)
+            }
+            _ => None,
+        }
+    }
+
+    pub fn resume(&mut self) -> Option<TimerEvent> {
+        let now = self.clock.now();
+        let cycle_info = self.current_cycle.as_mut()?;
+        let paused_at = cycle_info.paused_at.take()?;
+        cycle_info.start_time += now - paused_at;
+        cycle_info.escalating = false;
+        // The timer is not ticked while paused, a suspend in between does not matter.
+        self.last_tick = None;
+        Some(TimerEvent::Resumed)
+    }
+
+    pub fn reset(&mut self) -> Option<TimerEvent> {
+        let now = self.clock.now();
+        let cycle_info = self.current_cycle.as_mut()?;
+        cycle_info.start_time = now;
+        cycle_info.elapsed_offset = Duration::ZERO;
+        cycle_info.paused_at = cycle_info.paused_at.map(|_| now);
+        cycle_info.awaiting_confirmation_since =
+            cycle_info.awaiting_confirmation_since.map(|_| now);
+        cycle_info.escalating = false;
+        Some(TimerEvent::Reset)
+    }
+
+    /// End the current cycle early and switch to the other stance.
+    pub fn skip(&mut self, settings: &Settings) -> Option<TimerEvent> {
+        let cycle_info = self.current_cycle.take()?;
+        self.finish_cycle(&cycle_info, CycleEnd::Skip);
+        Some(self.switch_stance(settings, cycle_info.stance))
+    }
+
+    /// Postpone the latest stance change by going back to the previous stance for `by`.
+    ///
+    /// Only a stance change of the timer, which was neither acknowledged nor snoozed yet, can
+    /// be snoozed. Once the snoozed cycle ran out, the stance changes again as usual.
+    pub fn snooze(&mut self, by: Duration) -> Option<TimerEvent> {
+        let now = self.clock.now();
+        let snoozed_cycle = self.current_cycle.as_ref()?;
+        if !snoozed_cycle.from_stance_change || snoozed_cycle.acknowledged || snoozed_cycle.snoozed
+        {
+            return None;
+        }
+        let snoozed_cycle = snoozed_cycle.clone();
+        self.finish_cycle(&snoozed_cycle, CycleEnd::Snooze);
+
+        let started_at = self.clock.system_time();
+        let id = self.next_cycle_id();
+        let cycle_info = self.current_cycle.as_mut()?;
+        cycle_info.id = id;
+        cycle_info.stance = Stance::inverted(cycle_info.stance);
+        cycle_info.start_time = now;
+        cycle_info.elapsed_offset = Duration::ZERO;
+        cycle_info.started_at = started_at;
+        cycle_info.duration = by;
+        cycle_info.paused_at = None;
+        cycle_info.acknowledged = false;
+        cycle_info.awaiting_confirmation_since = None;
+        cycle_info.escalating = false;
+        cycle_info.snoozed = true;
+        cycle_info.reminders_sent = 0;
+        cycle_info.last_notified_at = now;
+        Some(TimerEvent::Snoozed {
+            stance: cycle_info.stance,
+            duration: by,
+        })
+    }
+
+    /// Use the duration of the settings for the current cycle, keeping the time it already ran.
+    ///
+    /// Snoozed cycles keep their duration. If the new duration already elapsed, the stance
+    /// changes with the next tick.
+    pub fn recalculate_duration(&mut self, settings: &Settings) {
+        if let Some(cycle_info) = self.current_cycle.as_mut()
+            && !cycle_info.snoozed
+        {
+            cycle_info.duration =
+                Duration::from_mins(settings.get_duration_for_stance(&cycle_info.stance));
+        }
+    }
+
+    /// Mark the stance change, which started the current cycle, as done by the user.
+    ///
+    /// Starts the countdown, if the cycle waits for confirmation.
+    pub fn acknowledge(&mut self) -> Option<TimerEvent> {
+        let now = self.clock.now();
+        let cycle_info = self.current_cycle.as_mut()?;
+        if cycle_info.acknowledged {
+            return None;
+        }
+        cycle_info.acknowledged = true;
+        cycle_info.escalating = false;
+        if let Some(awaiting_since) = cycle_info.awaiting_confirmation_since.take() {
+            cycle_info.start_time += now - awaiting_since;
+        }
+        Some(TimerEvent::Acknowledged)
+    }
+
+    /// Advance the timer to the current time of the clock.
+    ///
+    /// Switches to the next stance, when the current cycle ran out. With
+    /// `confirm_stance_change` set, the new cycle waits for confirmation.
+    ///
+    /// Until the user interacts with a cycle started this way, reminders are emitted according
+    /// to the escalation policy. They are always emitted while awaiting confirmation.
+    ///
+    /// A suspend of the system since the last tick is handled according to the wake policy
+    /// first.
+    pub fn tick(&mut self, settings: &Settings) -> Option<TimerEvent> {
+        let now = self.clock.now();
+        self.current_cycle.as_ref()?;
+        if let Some(slept) = self.detect_suspend() {
+            return Some(self.wake(settings, slept));
+        }
+
+        let cycle_info = self.current_cycle.as_mut()?;
+        if cycle_info.is_paused() {
+            return None;
+        }
+
+        let next_step = cycle_info.reminders_sent + 1;
+        if cycle_info.escalating
+            && (cycle_info.is_awaiting_confirmation() || settings.escalation.enabled)
+            && now - cycle_info.last_notified_at >= settings.escalation.delay_before_step(next_step)
+        {
+            cycle_info.reminders_sent = next_step;
+            cycle_info.last_notified_at = now;
+            return Some(TimerEvent::Reminder {
+                stance: cycle_info.stance,
+                step: settings.escalation.step(next_step),
+            });
+        }
+
+        if cycle_info.is_awaiting_confirmation() || !cycle_info.remaining(now).is_zero() {
+            return None;
+        }
+        let ended_cycle = cycle_info.clone();
+        Some(self.end_ran_out_cycle(settings, &ended_cycle, now))
+    }
+
+    /// Finish the cycle, whose countdown ran out, and switch to the next stance.
+    fn end_ran_out_cycle(
+        &mut self,
+        settings: &Settings,
+        ended_cycle: &TimerCycleInfo,
+        now: Instant,
+    ) -> TimerEvent {
+        self.finish_cycle(ended_cycle, CycleEnd::Natural);
+        let event = self.switch_stance(settings, ended_cycle.stance);
+        if let Some(cycle_info) = &mut self.current_cycle {
+            cycle_info.from_stance_change = true;
+            cycle_info.escalating = true;
+            if settings.confirm_stance_change {
+                cycle_info.awaiting_confirmation_since = Some(now);
+            }
+        }
+        event
+    }
+
+    /// Time the system was suspended since the last tick.
+    ///
+    /// The monotonic clock stops during suspend while the wall clock goes on, so a suspend
+    /// shows as the wall clock advancing further than the monotonic clock. A wall clock set
+    /// forward by more than [`SUSPEND_THRESHOLD`] looks the same and is treated as suspend too;
+    /// a wall clock set back is ignored.
+    fn detect_suspend(&mut self) -> Option<Duration> {
+        let now = self.clock.now();
+        let system_now = self.clock.system_time();
+        let (last_now, last_system) = self.last_tick.replace((now, system_now))?;
+
+        let wall_delta = system_now.duration_since(last_system).unwrap_or_default();
+        let slept = wall_delta.saturating_sub(now - last_now);
+        (slept >= SUSPEND_THRESHOLD).then_some(slept)
+    }
+
+    fn wake(&mut self, settings: &Settings, slept: Duration) -> TimerEvent {
+        let policy = settings.wake_policy;
+        match policy {
+            WakePolicy::Restart => {
+                if let Some(cycle_info) = self.current_cycle.take() {
+                    self.finish_cycle(&cycle_info, CycleEnd::Suspend);
+                }
+                self.current_cycle =
+                    Some(self.new_cycle(settings, settings.profile().start_stance));
+            }
+            WakePolicy::Continue => {
+                let now = self.clock.now();
+                if let Some(cycle_info) = self.current_cycle.as_mut()
+                    && !cycle_info.is_paused()
+                    && !cycle_info.is_awaiting_confirmation()
+                {
+                    let remaining = cycle_info.remaining(now);
+                    cycle_info.elapsed_offset += slept.min(remaining);
+                    // Time asleep after the cycle ran out was not spent in it.
+                    if slept >= remaining {
+                        let ended_cycle = cycle_info.clone();
+                        return self.end_ran_out_cycle(settings, &ended_cycle, now);
+                    }
+                }
+            }
+            WakePolicy::Break => {
+                if let Some(cycle_info) = self.current_cycle.as_mut() {
+                    cycle_info.escalating = false;
+                }
+            }
+        }
+        TimerEvent::Woke { slept, policy }
+    }
+
+    fn switch_stance(&mut self, settings: &Settings, current_stance: Stance) -> TimerEvent {
+        let cycle_info = self.new_cycle(settings, Stance::inverted(current_stance));
+        let event = TimerEvent::StanceChanged {
+            stance: cycle_info.stance,
+            duration: cycle_info.duration,
+        };
+        self.current_cycle = Some(cycle_info);
+        event
+    }
+
+    fn finish_cycle(&mut self, cycle_info: &TimerCycleInfo, end: CycleEnd) {
+        self.finished_cycles.push(FinishedCycle {
+            stance: cycle_info.stance,
+            profile: cycle_info.profile.clone(),
+            started_at: cycle_info.started_at,
+            planned_duration: cycle_info.duration,
+            actual_duration: cycle_info.active_duration(self.clock.now()),
+            end,
+            acknowledged: cycle_info.acknowledged,
+            reminders: cycle_info.reminders_sent,
+        });
+    }
+
+    fn next_cycle_id(&mut self) -> CycleId {
+        self.next_cycle_id += 1;
+        self.next_cycle_id
+    }
+
+    fn new_cycle(&mut self, settings: &Settings, stance: Stance) -> TimerCycleInfo {
+        let now = self.clock.now();
+        TimerCycleInfo {
+            id: self.next_cycle_id(),
+            start_time: now,
+            elapsed_offset: Duration::ZERO,
+            started_at: self.clock.system_time(),
+            duration: Duration::from_mins(settings.get_duration_for_stance(&stance)),
+            stance,
+            profile: settings.active_profile.clone(),
+            paused_at: None,
+            acknowledged: false,
+            awaiting_confirmation_since: None,
+            escalating: false,
+            from_stance_change: false,
+            snoozed: false,
+            reminders_sent: 0,
+            last_notified_at: now,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::{EscalationPolicy, Profile, WakePolicy};
+
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    /// Clock which only moves forward when told to.
+    #[derive(Debug, Clone)]
+    pub struct FakeClock {
+        base: Instant,
+        system_base: Rc<Cell<SystemTime>>,
+        offset: Rc<Cell<Duration>>,
+    }
+
+    impl FakeClock {
+        pub fn new() -> Self {
+            Self {
+                base: Instant::now(),
+                system_base: Rc::new(Cell::new(
+                    SystemTime::UNIX_EPOCH + Duration::from_hours(24 * 365 * 50),
+                )),
+                offset: Rc::new(Cell::new(Duration::ZERO)),
+            }
+        }
+
+        pub fn advance(&self, by: Duration) {
+            self.offset.set(self.offset.get() + by);
+        }
+
+        /// Let only the wall clock advance, like during a suspend of the system.
+        pub fn suspend(&self, by: Duration) {
+            self.system_base.set(self.system_base.get() + by);
+        }
+
+        pub fn set_wall_clock_back(&self, by: Duration) {
+            self.system_base.set(self.system_base.get() - by);
+        }
+
+        /// Clock of a new process, started when the wall clock shows `system_time`.
+        ///
+        /// Its monotonic time is unrelated to the one of this clock, like after a reboot.
+        pub fn restarted_at(system_time: SystemTime) -> Self {
+            Self {
+                base: Instant::now(),
+                system_base: Rc::new(Cell::new(system_time)),
+                offset: Rc::new(Cell::new(Duration::ZERO)),
+            }
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.base + self.offset.get()
+        }
+
+        fn system_time(&self) -> SystemTime {
+            self.system_base.get() + self.offset.get()
+        }
+    }
+
+    const SECOND: Duration = Duration::from_secs(1);
+    const MINUTE: Duration = Duration::from_mins(1);
+    const HOUR: Duration = Duration::from_hours(1);
+
+    fn settings(sit: u64, stand: u64, start_stance: Stance) -> Settings {
+        let mut settings = Settings::default();
+        *settings.profile_mut() = Profile {
+            sit_duration_as_min: sit,
+            stand_duration_as_min: stand,
+            start_stance,
+        };
+        settings
+    }
+
+    fn timer() -> (Timer<FakeClock>, FakeClock) {
+        let clock = FakeClock::new();
+        (Timer::new(clock.clone()), clock)
+    }
+
+    /// Tick the timer once per second for the given span and collect all stance changes.
+    fn run_for(
+        timer: &mut Timer<FakeClock>,
+        clock: &FakeClock,
+        settings: &Settings,
+        span: Duration,
+    ) -> Vec<Stance> {
+        let mut changes = Vec::new();
+        for _ in 0..span.as_secs() {
+            clock.advance(SECOND);
+            if let Some(TimerEvent::StanceChanged { stance, .. }) = timer.tick(settings) {
+                changes.push(stance);
+            }
+        }
+        changes
+    }
+
+    #[test]
+    fn start_uses_start_stance_and_its_duration() {
+        let (mut timer, _) = timer();
+        let settings = settings(45, 15, Stance::Standing);
+
+        assert_eq!(
+            timer.start(&settings),
+            Some(TimerEvent::Started {
+                stance: Stance::Standing,
+                duration: 15 * MINUTE,
+            })
+        );
+        assert_eq!(timer.remaining(), Some(15 * MINUTE));
+        assert_eq!(timer.start(&settings), None);
+    }
+
+    #[test]
+    fn tick_switches_stance_exactly_when_cycle_runs_out() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+
+        clock.advance(40 * MINUTE - SECOND);
+        assert_eq!(timer.tick(&settings), None);
+        assert_eq!(timer.remaining(), Some(SECOND));
+
+        clock.advance(SECOND);
+        assert_eq!(
+            timer.tick(&settings),
+            Some(TimerEvent::StanceChanged {
+                stance: Stance::Standing,
+                duration: 20 * MINUTE,
+            })
+        );
+        assert_eq!(timer.remaining(), Some(20 * MINUTE));
+    }
+
+    #[test]
+    fn full_work_day_alternates_stances() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+
+        let changes = run_for(&mut timer, &clock, &settings, 8 * HOUR);
+
+        assert_eq!(changes.len(), 16);
+        for (idx, stance) in changes.iter().enumerate() {
+            let expected = if idx % 2 == 0 {
+                Stance::Standing
+            } else {
+                Stance::Sitting
+            };
+            assert_eq!(*stance, expected);
+        }
+        assert_eq!(timer.current_cycle().unwrap().stance, Stance::Sitting);
+        assert_eq!(timer.remaining(), Some(40 * MINUTE));
+    }
+
+    #[test]
+    fn day_with_skips_shifts_following_cycles() {
+        let (mut timer, clock) = timer();
+        let settings = settings(45, 15, Stance::Sitting);
+        timer.start(&settings);
+
+        assert_eq!(run_for(&mut timer, &clock, &settings, 10 * MINUTE), vec![]);
+        assert_eq!(
+            timer.skip(&settings),
+            Some(TimerEvent::StanceChanged {
+                stance: Stance::Standing,
+                duration: 15 * MINUTE,
+            })
+        );
+
+        // Standing 15 min, then sitting 45 min, then standing again.
+        let changes = run_for(&mut timer, &clock, &settings, HOUR);
+        assert_eq!(changes, vec![Stance::Sitting, Stance::Standing]);
+        assert_eq!(timer.current_cycle().unwrap().stance, Stance::Standing);
+        assert_eq!(timer.remaining(), Some(15 * MINUTE));
+    }
+
+    #[test]
+    fn skip_and_tick_do_nothing_when_stopped() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+
+        assert_eq!(timer.skip(&settings), None);
+        assert_eq!(run_for(&mut timer, &clock, &settings, HOUR), vec![]);
+        assert_eq!(timer.remaining(), None);
+    }
+
+    #[test]
+    fn stop_discards_cycle_and_restart_uses_start_stance() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        run_for(&mut timer, &clock, &settings, 50 * MINUTE);
+        assert_eq!(timer.current_cycle().unwrap().stance, Stance::Standing);
+
+        assert_eq!(timer.stop(), Some(TimerEvent::Stopped));
+        assert_eq!(timer.stop(), None);
+        assert!(!timer.is_running());
+        assert_eq!(run_for(&mut timer, &clock, &settings, 2 * HOUR), vec![]);
+
+        timer.start(&settings);
+        assert_eq!(timer.current_cycle().unwrap().stance, Stance::Sitting);
+        assert_eq!(timer.remaining(), Some(40 * MINUTE));
+    }
+
+    #[test]
+    fn pause_freezes_remaining_time_until_resumed() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        clock.advance(10 * MINUTE);
+
+        assert_eq!(timer.pause(), Some(TimerEvent::Paused));
+        assert_eq!(timer.pause(), None);
+        assert!(timer.is_paused());
+        assert_eq!(run_for(&mut timer, &clock, &settings, 2 * HOUR), vec![]);
+        assert_eq!(timer.remaining(), Some(30 * MINUTE));
+
+        assert_eq!(timer.resume(), Some(TimerEvent::Resumed));
+        assert_eq!(timer.resume(), None);
+        assert_eq!(timer.remaining(), Some(30 * MINUTE));
+
+        let changes = run_for(&mut timer, &clock, &settings, 30 * MINUTE);
+        assert_eq!(changes, vec![Stance::Standing]);
+    }
+
+    #[test]
+    fn reset_restarts_current_cycle() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        clock.advance(25 * MINUTE);
+
+        assert_eq!(timer.reset(), Some(TimerEvent::Reset));
+        assert_eq!(timer.remaining(), Some(40 * MINUTE));
+        assert_eq!(timer.current_cycle().unwrap().stance, Stance::Sitting);
+
+        timer.pause();
+        clock.advance(5 * MINUTE);
+        timer.reset();
+        assert!(timer.is_paused());
+        assert_eq!(timer.remaining(), Some(40 * MINUTE));
+    }
+
+    #[test]
+    fn recalculated_duration_keeps_elapsed_time() {
+        let (mut timer, clock) = timer();
+        timer.start(&settings(40, 20, Stance::Sitting));
+        clock.advance(10 * MINUTE);
+
+        timer.recalculate_duration(&settings(30, 20, Stance::Sitting));
+        assert_eq!(timer.remaining(), Some(20 * MINUTE));
+
+        let shorter = settings(5, 20, Stance::Sitting);
+        timer.recalculate_duration(&shorter);
+        assert_eq!(timer.remaining(), Some(Duration::ZERO));
+        assert!(matches!(
+            timer.tick(&shorter),
+            Some(TimerEvent::StanceChanged {
+                stance: Stance::Standing,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn recalculation_keeps_snoozed_duration() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        run_for(&mut timer, &clock, &settings, 40 * MINUTE);
+        timer.snooze(5 * MINUTE);
+
+        timer.recalculate_duration(&settings);
+        assert_eq!(timer.remaining(), Some(5 * MINUTE));
+    }
+
+    #[test]
+    fn snooze_postpones_stance_change() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        assert_eq!(
+            run_for(&mut timer, &clock, &settings, 40 * MINUTE),
+            vec![Stance::Standing]
+        );
+
+        clock.advance(30 * SECOND);
+        assert_eq!(
+            timer.snooze(5 * MINUTE),
+            Some(TimerEvent::Snoozed {
+                stance: Stance::Sitting,
+                duration: 5 * MINUTE,
+            })
+        );
+        assert_eq!(timer.remaining(), Some(5 * MINUTE));
+
+        let changes = run_for(&mut timer, &clock, &settings, 5 * MINUTE);
+        assert_eq!(changes, vec![Stance::Standing]);
+        assert_eq!(timer.remaining(), Some(20 * MINUTE));
+    }
+
+    #[test]
+    fn stance_change_can_be_snoozed_only_once() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        run_for(&mut timer, &clock, &settings, 40 * MINUTE);
+
+        assert!(timer.snooze(5 * MINUTE).is_some());
+        assert_eq!(timer.snooze(5 * MINUTE), None);
+        assert_eq!(timer.current_cycle().unwrap().stance, Stance::Sitting);
+        assert_eq!(timer.remaining(), Some(5 * MINUTE));
+        assert_eq!(timer.take_finished_cycles().len(), 2);
+    }
+
+    #[test]
+    fn only_unanswered_stance_changes_can_be_snoozed() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        assert_eq!(timer.snooze(5 * MINUTE), None);
+
+        run_for(&mut timer, &clock, &settings, 40 * MINUTE);
+        timer.acknowledge();
+        assert_eq!(timer.snooze(5 * MINUTE), None);
+
+        timer.skip(&settings);
+        assert_eq!(timer.snooze(5 * MINUTE), None);
+        assert_eq!(timer.current_cycle().unwrap().stance, Stance::Sitting);
+    }
+
+    #[test]
+    fn answers_of_earlier_cycles_are_stale() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        let started_id = timer.current_cycle().unwrap().id;
+        assert!(timer.is_current_cycle(started_id));
+
+        run_for(&mut timer, &clock, &settings, 40 * MINUTE);
+        let changed_id = timer.current_cycle().unwrap().id;
+        assert!(!timer.is_current_cycle(started_id));
+        assert!(timer.is_current_cycle(changed_id));
+
+        timer.pause();
+        timer.reset();
+        assert!(timer.is_current_cycle(changed_id));
+        timer.resume();
+        timer.snooze(5 * MINUTE);
+        assert!(!timer.is_current_cycle(changed_id));
+        timer.skip(&settings);
+        timer.stop();
+        assert!(!timer.is_current_cycle(changed_id));
+    }
+
+    #[test]
+    fn acknowledge_marks_current_cycle_only() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+
+        assert_eq!(timer.acknowledge(), None);
+        timer.start(&settings);
+        assert_eq!(timer.acknowledge(), Some(TimerEvent::Acknowledged));
+        assert_eq!(timer.acknowledge(), None);
+        assert!(timer.current_cycle().unwrap().acknowledged);
+
+        run_for(&mut timer, &clock, &settings, 40 * MINUTE);
+        assert!(!timer.current_cycle().unwrap().acknowledged);
+    }
+
+    fn confirm_mode_settings(sit: u64, stand: u64) -> Settings {
+        Settings {
+            confirm_stance_change: true,
+            ..settings(sit, stand, Stance::Sitting)
+        }
+    }
+
+    #[test]
+    fn confirm_mode_waits_for_acknowledgement() {
+        let (mut timer, clock) = timer();
+        let settings = confirm_mode_settings(40, 20);
+        timer.start(&settings);
+
+        assert_eq!(
+            run_for(&mut timer, &clock, &settings, 40 * MINUTE),
+            vec![Stance::Standing]
+        );
+        assert!(timer.current_cycle().unwrap().is_awaiting_confirmation());
+        assert_eq!(run_for(&mut timer, &clock, &settings, 3 * HOUR), vec![]);
+        assert_eq!(timer.remaining(), Some(20 * MINUTE));
+
+        assert_eq!(timer.acknowledge(), Some(TimerEvent::Acknowledged));
+        assert!(!timer.current_cycle().unwrap().is_awaiting_confirmation());
+        clock.advance(5 * MINUTE);
+        assert_eq!(timer.remaining(), Some(15 * MINUTE));
+        assert_eq!(
+            run_for(&mut timer, &clock, &settings, 15 * MINUTE),
+            vec![Stance::Sitting]
+        );
+    }
+
+    #[test]
+    fn confirm_mode_reminds_on_escalating_interval() {
+        let (mut timer, clock) = timer();
+        let settings = confirm_mode_settings(40, 20);
+        timer.start(&settings);
+        clock.advance(40 * MINUTE);
+        timer.tick(&settings);
+
+        let mut reminders_at = Vec::new();
+        for minute in 1..=60 {
+            clock.advance(MINUTE);
+            if let Some(TimerEvent::Reminder { step, .. }) = timer.tick(&settings) {
+                assert_eq!(step.step as usize, reminders_at.len() + 1);
+                // Escalation is disabled, so reminders stay plain.
+                assert!(!step.critical && !step.raise_window);
+                reminders_at.push(minute);
+            }
+        }
+
+        assert_eq!(reminders_at, vec![1, 3, 7, 15, 31, 47]);
+    }
+
+    #[test]
+    fn confirm_mode_is_not_paused_and_cannot_be_paused() {
+        let (mut timer, clock) = timer();
+        let settings = confirm_mode_settings(40, 20);
+        timer.start(&settings);
+        clock.advance(40 * MINUTE);
+        timer.tick(&settings);
+
+        assert!(!timer.is_paused());
+        assert_eq!(timer.pause(), None);
+    }
+
+    #[test]
+    fn confirm_mode_skip_and_snooze_start_countdown() {
+        let (mut timer, clock) = timer();
+        let settings = confirm_mode_settings(40, 20);
+        timer.start(&settings);
+        clock.advance(40 * MINUTE);
+        timer.tick(&settings);
+
+        timer.snooze(5 * MINUTE);
+        assert!(!timer.current_cycle().unwrap().is_awaiting_confirmation());
+        assert_eq!(timer.current_cycle().unwrap().stance, Stance::Sitting);
+        clock.advance(5 * MINUTE);
+        timer.tick(&settings);
+        assert!(timer.current_cycle().unwrap().is_awaiting_confirmation());
+
+        timer.skip(&settings);
+        assert!(!timer.current_cycle().unwrap().is_awaiting_confirmation());
+        clock.advance(MINUTE);
+        assert_eq!(timer.remaining(), Some(39 * MINUTE));
+    }
+
+    fn escalation_settings(sit: u64, stand: u64) -> Settings {
+        Settings {
+            escalation: EscalationPolicy {
+                enabled: true,
+                repeat_after_as_min: 2,
+                max_repeat_interval_as_min: 4,
+                critical_from_step: 2,
+                raise_window_from_step: 3,
+            },
+            ..settings(sit, stand, Stance::Sitting)
+        }
+    }
+
+    /// Tick the timer once per second for the given span and collect all reminder steps.
+    fn reminders_for(
+        timer: &mut Timer<FakeClock>,
+        clock: &FakeClock,
+        settings: &Settings,
+        span: Duration,
+    ) -> Vec<EscalationStep> {
+        let mut steps = Vec::new();
+        for _ in 0..span.as_secs() {
+            clock.advance(SECOND);
+            if let Some(TimerEvent::Reminder { step, .. }) = timer.tick(settings) {
+                steps.push(step);
+            }
+        }
+        steps
+    }
+
+    #[test]
+    fn escalation_repeats_and_raises_ignored_stance_change() {
+        let (mut timer, clock) = timer();
+        let settings = escalation_settings(10, 20);
+        timer.start(&settings);
+
+        // No reminders for the cycle started by the user.
+        assert!(reminders_for(&mut timer, &clock, &settings, 10 * MINUTE).is_empty());
+        assert_eq!(timer.current_cycle().unwrap().stance, Stance::Standing);
+
+        let steps = reminders_for(&mut timer, &clock, &settings, 19 * MINUTE);
+        // After 2, 6, 10, 14 and 18 min.
+        assert_eq!(steps.len(), 5);
+        assert_eq!(
+            steps[0],
+            EscalationStep {
+                step: 1,
+                critical: false,
+                raise_window: false,
+            }
+        );
+        assert!(steps[1].critical && !steps[1].raise_window);
+        assert!(steps[2..].iter().all(|s| s.critical && s.raise_window));
+        assert_eq!(timer.current_cycle().unwrap().reminders_sent, 5);
+    }
+
+    #[test]
+    fn escalation_stops_once_user_interacts() {
+        let (mut timer, clock) = timer();
+        let settings = escalation_settings(10, 20);
+        timer.start(&settings);
+        clock.advance(10 * MINUTE);
+        timer.tick(&settings);
+
+        assert_eq!(
+            reminders_for(&mut timer, &clock, &settings, 3 * MINUTE).len(),
+            1
+        );
+        timer.pause();
+        timer.resume();
+        assert!(reminders_for(&mut timer, &clock, &settings, 15 * MINUTE).is_empty());
+
+        // The next stance change escalates again, until acknowledged.
+        clock.advance(2 * MINUTE);
+        timer.tick(&settings);
+        assert_eq!(timer.current_cycle().unwrap().stance, Stance::Sitting);
+        assert_eq!(
+            reminders_for(&mut timer, &clock, &settings, 3 * MINUTE).len(),
+            1
+        );
+        timer.acknowledge();
+        assert!(reminders_for(&mut timer, &clock, &settings, 5 * MINUTE).is_empty());
+    }
+
+    #[test]
+    fn disabled_escalation_sends_no_reminders() {
+        let (mut timer, clock) = timer();
+        let settings = settings(10, 20, Stance::Sitting);
+        timer.start(&settings);
+
+        assert!(reminders_for(&mut timer, &clock, &settings, 2 * HOUR).is_empty());
+    }
+
+    #[test]
+    fn finished_cycles_record_how_and_when_they_ended() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        let day_start = clock.system_time();
+        timer.start(&settings);
+
+        run_for(&mut timer, &clock, &settings, 40 * MINUTE);
+        clock.advance(5 * MINUTE);
+        timer.pause();
+        clock.advance(HOUR);
+        timer.resume();
+        clock.advance(2 * MINUTE);
+        timer.snooze(5 * MINUTE);
+        clock.advance(2 * MINUTE);
+        timer.skip(&settings);
+        clock.advance(MINUTE);
+        timer.stop();
+
+        let finished = timer.take_finished_cycles();
+        let summary: Vec<_> = finished
+            .iter()
+            .map(|c| (c.stance, c.planned_duration, c.actual_duration, c.end))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Stance::Sitting, 40 * MINUTE, 40 * MINUTE, CycleEnd::Natural),
+                (Stance::Standing, 20 * MINUTE, 7 * MINUTE, CycleEnd::Snooze),
+                (Stance::Sitting, 5 * MINUTE, 2 * MINUTE, CycleEnd::Skip),
+                (Stance::Standing, 20 * MINUTE, MINUTE, CycleEnd::Stop),
+            ]
+        );
+        assert_eq!(finished[0].started_at, day_start);
+        assert_eq!(finished[1].started_at, day_start + 40 * MINUTE);
+        assert_eq!(
+            finished[3].started_at,
+            day_start + 40 * MINUTE + 7 * MINUTE + HOUR + 2 * MINUTE
+        );
+        assert!(timer.take_finished_cycles().is_empty());
+    }
+
+    #[test]
+    fn settings_changes_apply_from_next_cycle() {
+        let (mut timer, clock) = timer();
+        let mut settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        settings.profile_mut().stand_duration_as_min = 5;
+
+        assert_eq!(timer.remaining(), Some(40 * MINUTE));
+        let changes = run_for(&mut timer, &clock, &settings, 45 * MINUTE);
+        assert_eq!(changes, vec![Stance::Standing, Stance::Sitting]);
+    }
+
+    #[test]
+    fn cycles_keep_profile_they_started_with() {
+        let (mut timer, clock) = timer();
+        let mut settings = settings(40, 20, Stance::Sitting);
+        let started_profile = settings.active_profile.clone();
+        settings
+            .profiles
+            .insert("Home".to_string(), Profile::default());
+        timer.start(&settings);
+        settings.select_profile("Home");
+        clock.advance(10 * MINUTE);
+
+        let (mut timer, _, _) = restart(&timer, &clock, &settings, MINUTE, RestoreMode::Continue);
+        timer.skip(&settings);
+
+        let finished = timer.take_finished_cycles();
+        assert_eq!(finished[0].profile, started_profile);
+        assert_eq!(timer.current_cycle().unwrap().profile, "Home");
+    }
+
+    /// Save the current cycle and restore it in a new timer started `closed_for` later.
+    fn restart(
+        timer: &Timer<FakeClock>,
+        clock: &FakeClock,
+        settings: &Settings,
+        closed_for: Duration,
+        mode: RestoreMode,
+    ) -> (Timer<FakeClock>, FakeClock, Option<TimerEvent>) {
+        let saved = timer.save_cycle().unwrap();
+        let clock = FakeClock::restarted_at(clock.system_time() + closed_for);
+        let mut timer = Timer::new(clock.clone());
+        let event = timer.restore_cycle(settings, &saved, mode);
+        (timer, clock, event)
+    }
+
+    #[test]
+    fn continued_cycle_counts_time_while_closed() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        clock.advance(10 * MINUTE);
+        let started_at = timer.current_cycle().unwrap().started_at;
+
+        let (mut timer, clock, event) =
+            restart(&timer, &clock, &settings, 5 * MINUTE, RestoreMode::Continue);
+
+        assert_eq!(
+            event,
+            Some(TimerEvent::Restored {
+                stance: Stance::Sitting,
+                remaining: 25 * MINUTE,
+            })
+        );
+        assert_eq!(timer.current_cycle().unwrap().started_at, started_at);
+        let changes = run_for(&mut timer, &clock, &settings, 25 * MINUTE);
+        assert_eq!(changes, vec![Stance::Standing]);
+        assert_eq!(timer.take_finished_cycles()[0].actual_duration, 40 * MINUTE);
+    }
+
+    #[test]
+    fn continued_cycle_which_ran_out_counts_planned_duration() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        clock.advance(10 * MINUTE);
+
+        let (mut timer, clock, event) =
+            restart(&timer, &clock, &settings, 3 * HOUR, RestoreMode::Continue);
+
+        assert_eq!(
+            event,
+            Some(TimerEvent::StanceChanged {
+                stance: Stance::Standing,
+                duration: 20 * MINUTE,
+            })
+        );
+        let finished = timer.take_finished_cycles();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].actual_duration, 40 * MINUTE);
+        assert_eq!(finished[0].end, CycleEnd::Natural);
+        assert!(timer.current_cycle().unwrap().escalating);
+        clock.advance(MINUTE);
+        assert_eq!(timer.remaining(), Some(19 * MINUTE));
+    }
+
+    #[test]
+    fn paused_cycle_ignores_time_while_closed() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        clock.advance(10 * MINUTE);
+        timer.pause();
+
+        let (mut timer, _, _) = restart(&timer, &clock, &settings, 2 * HOUR, RestoreMode::Continue);
+
+        assert!(timer.is_paused());
+        assert_eq!(timer.remaining(), Some(30 * MINUTE));
+        timer.resume();
+        assert_eq!(timer.remaining(), Some(30 * MINUTE));
+    }
+
+    #[test]
+    fn fresh_cycle_starts_over() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Standing);
+        timer.start(&settings);
+        clock.advance(15 * MINUTE);
+
+        let (mut timer, clock, _) =
+            restart(&timer, &clock, &settings, 5 * MINUTE, RestoreMode::Fresh);
+
+        assert_eq!(timer.current_cycle().unwrap().stance, Stance::Standing);
+        assert_eq!(timer.remaining(), Some(20 * MINUTE));
+        clock.advance(MINUTE);
+        timer.reset();
+        assert_eq!(timer.remaining(), Some(20 * MINUTE));
+    }
+
+    #[test]
+    fn wall_clock_set_back_while_closed_counts_as_no_time() {
+        let (mut timer, clock) = timer();
+        let settings = settings(40, 20, Stance::Sitting);
+        timer.start(&settings);
+        clock.advance(10 * MINUTE);
+        let saved = timer.save_cycle().unwrap();
+
+        let clock = FakeClock::restarted_at(saved.saved_at - HOUR);
+        let mut timer = Timer::new(clock);
+        timer.restore_cycle(&settings, &saved, RestoreMode::Continue);
+
+        assert_eq!(timer.remaining(), Some(30 * MINUTE));
+        assert_eq!(
+            timer.restore_cycle(&settings, &saved, RestoreMode::Fresh),
+            None
+        );
+    }
+
+    fn wake_settings(wake_policy: WakePolicy) -> Settings {
+        Settings {
+            wake_policy,
+            ..settings(40, 20, Stance::Sitting)
+        }
+    }
+
+    #[test]
+    fn suspend_counts_as_cycle_time_when_continuing() {
+        let (mut timer, clock) = timer();
+        let settings = wake_settings(WakePolicy::Continue);
+        timer.start(&settings);
+        run_for(&mut timer, &clock, &settings, 10 * MINUTE);
+
+        clock.suspend(20 * MINUTE);
+        clock.advance(SECOND);
+
+        assert_eq!(
+            timer.tick(&settings),
+            Some(TimerEvent::Woke {
+                slept: 20 * MINUTE,
+                policy: WakePolicy::Continue,
+            })
+        );
+        assert_eq!(timer.remaining(), Some(10 * MINUTE - SECOND));
+        let changes = run_for(&mut timer, &clock, &settings, 10 * MINUTE);
+        assert_eq!(changes, vec![Stance::Standing]);
+    }
+
+    #[test]
+    fn suspend_longer_than_cycle_counts_planned_duration_when_continuing() {
+        let (mut timer, clock) = timer();
+        let settings = wake_settings(WakePolicy::Continue);
+        timer.start(&settings);
+        run_for(&mut timer, &clock, &settings, MINUTE);
+        timer.take_finished_cycles();
+
+        clock.suspend(8 * HOUR);
+        let changes = run_for(&mut timer, &clock, &settings, 2 * SECOND);
+
+        // The next cycle started on wake, one tick ago.
+        assert_eq!(changes, vec![Stance::Standing]);
+        assert_eq!(timer.remaining(), Some(20 * MINUTE - SECOND));
+        let finished = timer.take_finished_cycles();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].stance, Stance::Sitting);
+        assert_eq!(finished[0].actual_duration, 40 * MINUTE);
+    }
+
+    #[test]
+    fn suspend_counts_as_pause_when_taking_a_break() {
+        let (mut timer, clock) = timer();
+        let settings = wake_settings(WakePolicy::Break);
+        timer.start(&settings);
+        run_for(&mut timer, &clock, &settings, 10 * MINUTE);
+
+        clock.suspend(HOUR);
+        clock.advance(SECOND);
+
+        assert!(matches!(
+            timer.tick(&settings),
+            Some(TimerEvent::Woke {
+                policy: WakePolicy::Break,
+                ..
+            })
+        ));
+        assert_eq!(timer.remaining(), Some(30 * MINUTE - SECOND));
+    }
+
+    #[test]
+    fn suspend_restarts_with_start_stance() {
+        let (mut timer, clock) = timer();
+        let settings = wake_settings(WakePolicy::Restart);
+        timer.start(&settings);
+        run_for(&mut timer, &clock, &settings, 45 * MINUTE);
+        timer.take_finished_cycles();
+
+        clock.suspend(HOUR);
+        clock.advance(SECOND);
+        timer.tick(&settings);
+
+        let cycle_info = timer.current_cycle().unwrap();
+        assert_eq!(cycle_info.stance, Stance::Sitting);
+        assert_eq!(timer.remaining(), Some(40 * MINUTE));
+        let finished = timer.take_finished_cycles();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].stance, Stance::Standing);
+        assert_eq!(finished[0].end, CycleEnd::Suspend);
+        assert_eq!(finished[0].actual_duration, 5 * MINUTE + SECOND);
+    }
+
+    #[test]
+    fn small_or_backward_wall_clock_jumps_are_no_suspend() {
+        let (mut timer, clock) = timer();
+        let settings = wake_settings(WakePolicy::Restart);
+        timer.start(&settings);
+        run_for(&mut timer, &clock, &settings, MINUTE);
+
+        clock.suspend(SUSPEND_THRESHOLD - SECOND);
+        assert_eq!(run_for(&mut timer, &clock, &settings, SECOND), vec![]);
+        clock.set_wall_clock_back(2 * HOUR);
+        assert_eq!(run_for(&mut timer, &clock, &settings, SECOND), vec![]);
+
+        assert_eq!(timer.remaining(), Some(39 * MINUTE - 2 * SECOND));
+        assert!(timer.take_finished_cycles().is_empty());
+    }
+
+    #[test]
+    fn suspend_while_paused_or_awaiting_confirmation_is_not_counted() {
+        let (mut timer, clock) = timer();
+        let settings = Settings {
+            confirm_stance_change: true,
+            ..wake_settings(WakePolicy::Continue)
+        };
+        timer.start(&settings);
+        run_for(&mut timer, &clock, &settings, 10 * MINUTE);
+        timer.pause();
+        clock.suspend(HOUR);
+        clock.advance(MINUTE);
+        timer.resume();
+        run_for(&mut timer, &clock, &settings, SECOND);
+        assert_eq!(timer.remaining(), Some(30 * MINUTE - SECOND));
+
+        run_for(&mut timer, &clock, &settings, 30 * MINUTE);
+        assert!(timer.is_awaiting_confirmation());
+        clock.suspend(HOUR);
+        clock.advance(SECOND);
+        assert!(matches!(
+            timer.tick(&settings),
+            Some(TimerEvent::Woke { .. })
+        ));
+        timer.acknowledge();
+        assert_eq!(timer.remaining(), Some(20 * MINUTE));
+    }
+}